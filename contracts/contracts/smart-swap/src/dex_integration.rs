@@ -1,4 +1,6 @@
-use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+use soroban_sdk::{contracttype, xdr::ToXdr, Address, BytesN, Env, Symbol, Vec};
+
+use crate::DataKey;
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -9,6 +11,15 @@ pub struct DexConfig {
     pub fee_tier: u32,              // Fee tier in basis points
     pub min_liquidity: u64,         // Minimum liquidity required for swaps
     pub max_slippage_tolerance: u32, // Maximum allowed slippage in basis points
+    pub quote_validity_secs: u64,   // How long a quote stays executable
+    pub min_gas_estimate: u64,      // Floor on gas estimates so keepers aren't underpaid
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AssetType {
+    Native,          // Stellar native asset (XLM)
+    Issued(Option<Address>), // Issued asset, with its token contract once registered
 }
 
 #[contracttype]
@@ -16,6 +27,8 @@ pub struct DexConfig {
 pub struct SwapPath {
     pub token_in: Symbol,
     pub token_out: Symbol,
+    pub asset_in: AssetType,
+    pub asset_out: AssetType,
     pub intermediate_tokens: Vec<Symbol>, // For multi-hop swaps
     pub pool_addresses: Vec<Address>,     // Pool addresses for each hop
 }
@@ -40,6 +53,7 @@ pub struct SwapParams {
     pub amount_out_min: u64,
     pub to: Address,              // Recipient address
     pub deadline: u64,            // Transaction deadline
+    pub max_price_impact_bps: u32, // Maximum allowed price impact in basis points
 }
 
 #[contracttype]
@@ -60,6 +74,8 @@ pub struct PoolInfo {
     pub pool_address: Address,
     pub token_a: Symbol,
     pub token_b: Symbol,
+    pub asset_a: AssetType,
+    pub asset_b: AssetType,
     pub reserve_a: u64,
     pub reserve_b: u64,
     pub total_supply: u64,
@@ -135,6 +151,54 @@ impl StellarDexIntegration {
             }
         };
 
+        Self::execute_swap_with_quote(env, dex_config, swap_params, quote)
+    }
+
+    /// Gas a swap priced by `quote` is expected to consume
+    pub fn projected_gas_used(quote: &SwapQuote) -> u64 {
+        // Simulate some gas usage variation
+        quote.estimated_gas + (quote.estimated_gas / 10) // +10% variation
+    }
+
+    /// Deadline capped at the quote's validity so a swap never executes against stale pricing
+    pub fn effective_deadline(swap_params: &SwapParams, quote: &SwapQuote) -> u64 {
+        swap_params.deadline.min(quote.valid_until)
+    }
+
+    pub fn execute_swap_with_quote(
+        env: &Env,
+        dex_config: &DexConfig,
+        mut swap_params: SwapParams,
+        quote: SwapQuote,
+    ) -> SwapResult {
+        swap_params.deadline = Self::effective_deadline(&swap_params, &quote);
+
+        // Reject quotes that went stale before execution
+        if env.ledger().timestamp() > quote.valid_until {
+            return SwapResult {
+                success: false,
+                amount_in: 0,
+                amount_out: 0,
+                actual_price_impact: 0,
+                gas_used: 0,
+                transaction_hash: Symbol::new(env, ""),
+                error_message: Some(Symbol::new(env, "quote_expired")),
+            };
+        }
+
+        // Check price impact protection
+        if quote.price_impact > swap_params.max_price_impact_bps {
+            return SwapResult {
+                success: false,
+                amount_in: 0,
+                amount_out: 0,
+                actual_price_impact: quote.price_impact,
+                gas_used: 0,
+                transaction_hash: Symbol::new(env, ""),
+                error_message: Some(Symbol::new(env, "price_impact_too_high")),
+            };
+        }
+
         // Check slippage protection
         if quote.amount_out < swap_params.amount_out_min {
             return SwapResult {
@@ -172,13 +236,15 @@ impl StellarDexIntegration {
         // This would query the actual DEX contract for pool information
         // For demonstration, we'll simulate the response
 
-        let pool_address = Self::calculate_pool_address(env, &token_a, &token_b);
+        let pool_address = Self::calculate_pool_address(env, dex_config, &token_a, &token_b);
         
         // Simulate pool reserves based on asset types
         let (reserve_a, reserve_b) = Self::get_simulated_reserves(&token_a, &token_b);
 
         Ok(PoolInfo {
             pool_address,
+            asset_a: Self::resolve_asset_type(env, &token_a),
+            asset_b: Self::resolve_asset_type(env, &token_b),
             token_a: token_a.clone(),
             token_b: token_b.clone(),
             reserve_a,
@@ -216,21 +282,20 @@ impl StellarDexIntegration {
         Ok(total_liquidity >= dex_config.min_liquidity)
     }
 
-    pub fn estimate_gas(
-        env: &Env,
-        swap_params: &SwapParams,
-        swap_path: &SwapPath,
-    ) -> u64 {
-        // Base gas cost for a simple swap
+    /// Gas for a swap along `swap_path`, which only depends on the route and not on who
+    /// receives the output
+    pub fn estimate_gas(dex_config: &DexConfig, swap_path: &SwapPath) -> u64 {
+        // Base gas cost for a simple swap through one pool
         let base_gas = 100_000u64;
 
-        // Additional gas for each hop in multi-hop swaps
-        let hop_gas = swap_path.intermediate_tokens.len() as u64 * 50_000;
+        // Additional gas for each further pool the route passes through
+        let pool_count = swap_path.pool_addresses.len().max(1) as u64;
+        let hop_gas = (pool_count - 1) * 50_000;
 
         // Additional gas for complex token types
-        let token_complexity_gas = Self::estimate_token_complexity_gas(&swap_params.token_in, &swap_params.token_out);
+        let token_complexity_gas = Self::estimate_token_complexity_gas(&swap_path.asset_in, &swap_path.asset_out);
 
-        base_gas + hop_gas + token_complexity_gas
+        (base_gas + hop_gas + token_complexity_gas).max(dex_config.min_gas_estimate)
     }
 
     // Internal helper methods
@@ -256,7 +321,7 @@ impl StellarDexIntegration {
         Ok(())
     }
 
-    fn validate_swap_execution(env: &Env, params: &SwapParams) -> Result<(), Symbol> {
+    pub fn validate_swap_execution(env: &Env, params: &SwapParams) -> Result<(), Symbol> {
         let current_time = env.ledger().timestamp();
 
         if current_time > params.deadline {
@@ -279,9 +344,11 @@ impl StellarDexIntegration {
         // For simplicity, we'll implement direct swaps and one-hop swaps through major tokens
         
         // Try direct path first
-        let direct_pool = Self::calculate_pool_address(env, &token_in, &token_out);
+        let direct_pool = Self::calculate_pool_address(env, dex_config, &token_in, &token_out);
         if Self::pool_exists(env, &direct_pool) {
             return Ok(SwapPath {
+                asset_in: Self::resolve_asset_type(env, &token_in),
+                asset_out: Self::resolve_asset_type(env, &token_out),
                 token_in: token_in.clone(),
                 token_out: token_out.clone(),
                 intermediate_tokens: Vec::new(env),
@@ -301,8 +368,8 @@ impl StellarDexIntegration {
                 continue;
             }
 
-            let pool1 = Self::calculate_pool_address(env, &token_in, &intermediate);
-            let pool2 = Self::calculate_pool_address(env, &intermediate, &token_out);
+            let pool1 = Self::calculate_pool_address(env, dex_config, &token_in, &intermediate);
+            let pool2 = Self::calculate_pool_address(env, dex_config, &intermediate, &token_out);
 
             if Self::pool_exists(env, &pool1) && Self::pool_exists(env, &pool2) {
                 let mut intermediate_tokens = Vec::new(env);
@@ -313,6 +380,8 @@ impl StellarDexIntegration {
                 pool_addresses.push_back(pool2);
 
                 return Ok(SwapPath {
+                    asset_in: Self::resolve_asset_type(env, &token_in),
+                    asset_out: Self::resolve_asset_type(env, &token_out),
                     token_in,
                     token_out,
                     intermediate_tokens,
@@ -359,18 +428,7 @@ impl StellarDexIntegration {
             total_price_impact += price_impact;
         }
 
-        let estimated_gas = Self::estimate_gas(
-            env,
-            &SwapParams {
-                token_in: swap_path.token_in.clone(),
-                token_out: swap_path.token_out.clone(),
-                amount_in,
-                amount_out_min: current_amount,
-                to: Address::generate(env), // Placeholder
-                deadline: env.ledger().timestamp() + 300,
-            },
-            swap_path,
-        );
+        let estimated_gas = Self::estimate_gas(dex_config, swap_path);
 
         Ok(SwapQuote {
             amount_in,
@@ -378,7 +436,7 @@ impl StellarDexIntegration {
             price_impact: total_price_impact,
             estimated_gas,
             route: swap_path.clone(),
-            valid_until: env.ledger().timestamp() + 30, // 30 seconds validity
+            valid_until: env.ledger().timestamp() + dex_config.quote_validity_secs,
         })
     }
 
@@ -401,24 +459,22 @@ impl StellarDexIntegration {
         // amount_out = (amount_in * reserve_out) / (reserve_in + amount_in)
         // Apply fee: amount_in_with_fee = amount_in * (10000 - fee) / 10000
 
-        let fee_complement = 10000 - pool_info.fee_rate;
-        let amount_in_with_fee = (amount_in * fee_complement as u64) / 10000;
+        // Widen to u128, the simulated reserves times a large amount overflow u64
+        let fee_complement = 10000u32.saturating_sub(pool_info.fee_rate);
+        let amount_in_with_fee = amount_in as u128 * fee_complement as u128 / 10000;
 
-        let numerator = amount_in_with_fee * reserve_out;
-        let denominator = reserve_in + amount_in_with_fee;
-
-        if denominator == 0 {
-            return Err(Symbol::new(&pool_info.pool_address.env(), "calculation_error"));
+        if amount_in_with_fee == 0 {
+            return Err(Symbol::new(&pool_info.pool_address.env(), "amount_too_small_after_fee"));
         }
 
-        let amount_out = numerator / denominator;
+        let numerator = amount_in_with_fee * reserve_out as u128;
+        let denominator = reserve_in as u128 + amount_in_with_fee;
+
+        // Always below reserve_out, so it fits back into u64
+        let amount_out = (numerator / denominator) as u64;
 
         // Calculate price impact
-        let price_impact = if reserve_in > 0 {
-            ((amount_in * 10000) / reserve_in) as u32
-        } else {
-            10000 // 100% impact if no liquidity
-        };
+        let price_impact = (amount_in as u128 * 10000 / reserve_in as u128).min(u32::MAX as u128) as u32;
 
         Ok((amount_out, price_impact))
     }
@@ -435,8 +491,7 @@ impl StellarDexIntegration {
         let transaction_hash = Symbol::new(env, "simulated_tx_hash");
         let actual_amount_out = quote.amount_out;
 
-        // Simulate some gas usage variation
-        let gas_used = quote.estimated_gas + (quote.estimated_gas / 10); // +10% variation
+        let gas_used = Self::projected_gas_used(quote);
 
         Ok(SwapResult {
             success: true,
@@ -449,13 +504,43 @@ impl StellarDexIntegration {
         })
     }
 
-    fn calculate_pool_address(env: &Env, token_a: &Symbol, token_b: &Symbol) -> Address {
-        // In a real implementation, this would calculate the actual pool address
-        // based on the DEX's pool creation algorithm
-        
-        // For simulation, generate a deterministic address
-        let combined = format!("{}_{}_pool", token_a.to_string(), token_b.to_string());
-        Address::generate(env) // Placeholder - would be deterministic in real implementation
+    /// Address of the pool the factory deploys for a pair. The salt hashes the pair in
+    /// canonical order, so both directions resolve to the same pool.
+    pub fn calculate_pool_address(
+        env: &Env,
+        dex_config: &DexConfig,
+        token_a: &Symbol,
+        token_b: &Symbol,
+    ) -> Address {
+        let (first, second) = if token_a <= token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+
+        let mut pair = first.clone().to_xdr(env);
+        pair.append(&second.clone().to_xdr(env));
+        let salt: BytesN<32> = env.crypto().sha256(&pair).into();
+
+        let factory = dex_config
+            .factory_address
+            .clone()
+            .unwrap_or_else(|| dex_config.dex_contract_address.clone());
+        env.deployer().with_address(factory, salt).deployed_address()
+    }
+
+    /// Issued assets carry the token contract registered with `set_asset_token`. Unregistered
+    /// ones are still routed by symbol, without an address.
+    pub fn resolve_asset_type(env: &Env, asset_symbol: &Symbol) -> AssetType {
+        if *asset_symbol == Symbol::new(env, NATIVE_ASSET_SYMBOL) {
+            AssetType::Native
+        } else {
+            AssetType::Issued(
+                env.storage()
+                    .instance()
+                    .get(&DataKey::AssetToken(asset_symbol.clone())),
+            )
+        }
     }
 
     fn pool_exists(env: &Env, pool_address: &Address) -> bool {
@@ -464,6 +549,27 @@ impl StellarDexIntegration {
         true // Simplified for demonstration
     }
 
+    /// Instantaneous pool price of `token_in` in `token_out`, scaled by 7 decimals
+    pub fn get_spot_price(
+        env: &Env,
+        dex_config: &DexConfig,
+        token_in: Symbol,
+        token_out: Symbol,
+    ) -> Result<u64, Symbol> {
+        let pool_info = Self::get_pool_info(env, dex_config, token_in, token_out)?;
+
+        if pool_info.reserve_a == 0 || pool_info.reserve_b == 0 {
+            return Err(Symbol::new(env, "zero_reserves"));
+        }
+
+        let spot_price = (pool_info.reserve_b as u128 * 1_0000000) / pool_info.reserve_a as u128;
+        if spot_price > u64::MAX as u128 {
+            Ok(u64::MAX)
+        } else {
+            Ok(spot_price as u64)
+        }
+    }
+
     fn get_simulated_reserves(token_a: &Symbol, token_b: &Symbol) -> (u64, u64) {
         // Simulate realistic reserves for common trading pairs
         match (token_a.to_string().as_str(), token_b.to_string().as_str()) {
@@ -475,13 +581,13 @@ impl StellarDexIntegration {
         }
     }
 
-    fn estimate_token_complexity_gas(token_in: &Symbol, token_out: &Symbol) -> u64 {
+    fn estimate_token_complexity_gas(asset_in: &AssetType, asset_out: &AssetType) -> u64 {
         // Estimate additional gas based on token complexity
         let base_complexity = 10_000u64;
 
-        // Native tokens (like XLM) are cheaper
-        let in_complexity = if token_in.to_string() == "XLM" { 0 } else { base_complexity };
-        let out_complexity = if token_out.to_string() == "XLM" { 0 } else { base_complexity };
+        // The native asset skips the token contract call
+        let in_complexity = if *asset_in == AssetType::Native { 0 } else { base_complexity };
+        let out_complexity = if *asset_out == AssetType::Native { 0 } else { base_complexity };
 
         in_complexity + out_complexity
     }
@@ -498,6 +604,8 @@ impl DexConfigManager {
             fee_tier: 30,                    // 0.3% fee
             min_liquidity: 100_000_0000000,  // 100k XLM minimum liquidity
             max_slippage_tolerance: 1000,    // 10% maximum slippage
+            quote_validity_secs: QUOTE_VALIDITY_DURATION,
+            min_gas_estimate: DEFAULT_MIN_GAS_ESTIMATE,
         }
     }
 
@@ -514,14 +622,27 @@ impl DexConfigManager {
             return Err(Symbol::new(env, "slippage_tolerance_too_high"));
         }
 
+        if config.quote_validity_secs == 0 || config.quote_validity_secs > MAX_QUOTE_VALIDITY_DURATION {
+            return Err(Symbol::new(env, "invalid_quote_validity"));
+        }
+
+        if config.min_gas_estimate == 0 || config.min_gas_estimate > MAX_MIN_GAS_ESTIMATE {
+            return Err(Symbol::new(env, "invalid_min_gas_estimate"));
+        }
+
         Ok(())
     }
 }
 
 // Constants for DEX integration
+pub const NATIVE_ASSET_SYMBOL: &str = "XLM";
 pub const DEFAULT_FEE_TIER: u32 = 30;                    // 0.3%
 pub const MAX_FEE_TIER: u32 = 1000;                      // 10%
 pub const DEFAULT_MIN_LIQUIDITY: u64 = 100_000_0000000;  // 100k XLM
 pub const DEFAULT_MAX_SLIPPAGE: u32 = 1000;              // 10%
 pub const QUOTE_VALIDITY_DURATION: u64 = 30;             // 30 seconds
+pub const MAX_QUOTE_VALIDITY_DURATION: u64 = 300;        // 5 minutes
+pub const DEFAULT_MIN_GAS_ESTIMATE: u64 = 100_000;
+pub const MAX_MIN_GAS_ESTIMATE: u64 = 10_000_000;
+pub const MAX_PRICE_IMPACT_BPS: u32 = 10000;             // 100%
 pub const MAX_SWAP_AMOUNT: u64 = 1_000_000_0000000;      // 1M XLM
\ No newline at end of file
@@ -1,7 +1,7 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, Address, Env, Map, Symbol, Vec, log,
+    contract, contractimpl, contracttype, token, Address, Env, Map, Symbol, Vec, log,
 };
 
 mod swap_condition;
@@ -21,9 +21,26 @@ pub enum DataKey {
     OracleConfig,                      // OracleConfig
     DexConfig,                         // DexConfig
     Admin,                             // Address
-    PausedStatus,                      // bool
+    PausedStatus,                      // PauseFlags (cache of config.pause_flags for cheap reads)
     SupportedAssets,                   // Vec<Symbol>
     GlobalStats,                       // GlobalStats
+    ConditionOwner(u64),               // u64 -> Address (condition owner)
+    PendingAdmin,                      // Address
+    RetryQueue,                        // Vec<u64> (conditions to re-check after transient failures)
+    AssetVolume(Symbol),               // Symbol -> u64 (persistent, volume swapped per source asset)
+    AssetToken(Symbol),                // Symbol -> Address (token contract used for escrow)
+    ConditionEscrow(u64),              // u64 -> (Address, i128) (token and amount held for the condition)
+    ExecutionLog(u32),                 // u32 -> SwapExecution (persistent, append-only feed)
+    ExecutionLogLength,                // u32
+    KeeperRewards(Address),            // Address -> u64 (unclaimed keeper rewards, in reward token units)
+    RewardToken,                       // (Symbol, Address) (asset and token keeper rewards are paid in)
+    KeeperRewardPool,                  // u64 (reward tokens funded by the admin and not yet claimed)
+    PendingVolume(Symbol),             // Symbol -> u64 (unfilled amount across active conditions)
+    UserVolume(Address),               // Address -> u64 (persistent, cumulative executed volume)
+    FeeTiers,                          // Vec<FeeTier> (volume discount schedule, ascending)
+    AssetMeta(Symbol),                 // Symbol -> AssetMeta (per-asset decimals)
+    CollectedFees,                     // u64 (protocol fees not yet withdrawn)
+    PriceHistory(Symbol),              // Symbol -> Vec<PriceData> (persistent, rolling window of observed prices)
 }
 
 #[contracttype]
@@ -32,9 +49,68 @@ pub struct ContractConfig {
     pub admin: Address,
     pub oracle_config: OracleConfig,
     pub dex_config: DexConfig,
-    pub paused: bool,
+    pub pause_flags: PauseFlags,
     pub max_conditions_per_user: u32,
     pub min_condition_value: u64,
+    pub enforce_asset_whitelist: bool,
+    pub keeper_reward_bps: u32, // Share of swapped amount paid to the executing keeper
+    pub swap_deadline_secs: u64, // Seconds a submitted swap stays valid
+    pub max_deviation_bps: u32,  // Halt execution when price moves further than this from the reference
+    pub protocol_fee_bps: u32,   // Protocol share of swapped amount, recorded per execution
+    pub max_pending_per_asset: u64, // Cap on unfilled amount per source asset, 0 disables
+    pub min_check_interval_secs: u64, // Minimum seconds between checks of a condition, 0 disables
+    pub max_total_active_conditions: u64, // Protocol-wide cap on active conditions across all users
+    pub max_oracle_dex_divergence_bps: u32, // Reject fills whose DEX rate is further than this from the oracle, 0 disables
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PauseFlags {
+    pub create_paused: bool,
+    pub execute_paused: bool,
+    pub cancel_paused: bool,
+}
+
+impl PauseFlags {
+    pub fn all(paused: bool) -> Self {
+        Self {
+            create_paused: paused,
+            execute_paused: paused,
+            cancel_paused: paused,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.create_paused || self.execute_paused || self.cancel_paused
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Liveness {
+    pub is_active: bool,
+    pub is_expired: bool,
+    pub is_executable_now: bool,      // Trigger met, condition valid and execution not paused
+    pub next_eligible_time: Option<u64>, // None once the condition can never execute again
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConditionStatusReport {
+    pub status: Option<SwapStatus>, // None when the condition doesn't exist
+    pub current_price: Option<u64>, // None when the oracle couldn't serve a price
+    pub price_timestamp: Option<u64>, // When the oracle observed current_price
+    pub confidence: Option<u32>,      // Oracle confidence in current_price
+    pub would_execute: bool,        // Active with its trigger met at current_price
+    pub projected_output: u64,      // DEX quote for the next fill, 0 when unavailable
+    pub error: Option<Symbol>,      // Why the report is incomplete, if it is
+}
+
+// Operations that can be paused independently
+pub enum PausableOperation {
+    Create,
+    Execute,
+    Cancel,
 }
 
 #[contracttype]
@@ -42,11 +118,70 @@ pub struct ContractConfig {
 pub struct GlobalStats {
     pub total_conditions_created: u64,
     pub total_conditions_executed: u64,
-    pub total_volume_swapped: u64,
+    pub total_volume_swapped: u64, // Raw sum across all assets, see get_asset_volume for a per-asset breakdown
     pub total_fees_collected: u64,
     pub active_conditions_count: u64,
+    pub total_keeper_rewards: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatusCounts {
+    pub active: u64,
+    pub executed: u64,
+    pub cancelled: u64,
+    pub failed: u64,
+    pub expired: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpiryBuckets {
+    pub within_day: Vec<u64>,  // Expires in under 24 hours
+    pub within_week: Vec<u64>, // Expires in under 7 days
+    pub later: Vec<u64>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvariantReport {
+    pub stored_active_count: u64,          // GlobalStats.active_conditions_count
+    pub actual_active_count: u64,          // Active conditions found in the conditions map
+    pub active_count_matches: bool,
+    pub orphaned_user_entries: Vec<u64>,   // User index ids that are missing or owned by someone else
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetMeta {
+    pub decimals: u32, // Base units per whole unit as a power of ten
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeTier {
+    pub min_volume: u64, // Cumulative executed volume needed to reach the tier
+    pub fee_bps: u32,    // Protocol fee charged once reached
+}
+
+pub const DEFAULT_KEEPER_REWARD_BPS: u32 = 10; // 0.1% of swapped amount
+pub const DEFAULT_SWAP_DEADLINE_SECS: u64 = 300; // 5 minutes
+pub const MIN_SWAP_DEADLINE_SECS: u64 = 30;
+pub const MAX_SWAP_DEADLINE_SECS: u64 = 3600;
+pub const MAX_RETRY_QUEUE_SIZE: u32 = 100;
+pub const DEFAULT_MAX_DEVIATION_BPS: u32 = 10000; // Price more than doubled from the reference
+pub const DEFAULT_PROTOCOL_FEE_BPS: u32 = 5; // 0.05% of swapped amount
+pub const MAX_PROTOCOL_FEE_BPS: u32 = 100;   // 1% cap
+pub const MAX_FEE_REVENUE_SCAN: u32 = 1000;  // Execution records scanned per revenue query
+pub const MAX_EXECUTION_PAGE_SIZE: u32 = 50;
+pub const MAX_CONDITION_PAGE_SIZE: u32 = 50;
+pub const MAX_MIN_CHECK_INTERVAL_SECS: u64 = 3600;
+pub const DEFAULT_MAX_TOTAL_ACTIVE_CONDITIONS: u64 = 10_000;
+pub const MAX_FEE_TIERS: u32 = 10;
+pub const MAX_BATCH_CREATE_SIZE: u32 = 20;
+pub const EXPIRY_BUCKET_DAY_SECS: u64 = 86400;
+pub const EXPIRY_BUCKET_WEEK_SECS: u64 = 7 * 86400;
+
 #[contract]
 pub struct SmartSwap;
 
@@ -69,9 +204,18 @@ impl SmartSwap {
             admin: admin.clone(),
             oracle_config,
             dex_config,
-            paused: false,
+            pause_flags: PauseFlags::all(false),
             max_conditions_per_user: 50,
             min_condition_value: 10_0000000, // 10 XLM minimum
+            enforce_asset_whitelist: false,
+            keeper_reward_bps: DEFAULT_KEEPER_REWARD_BPS,
+            swap_deadline_secs: DEFAULT_SWAP_DEADLINE_SECS,
+            max_deviation_bps: DEFAULT_MAX_DEVIATION_BPS,
+            protocol_fee_bps: DEFAULT_PROTOCOL_FEE_BPS,
+            max_pending_per_asset: 0,
+            min_check_interval_secs: 0,
+            max_total_active_conditions: DEFAULT_MAX_TOTAL_ACTIVE_CONDITIONS,
+            max_oracle_dex_divergence_bps: 0,
         };
 
         env.storage().instance().set(&DataKey::Admin, &config);
@@ -85,22 +229,28 @@ impl SmartSwap {
             total_volume_swapped: 0,
             total_fees_collected: 0,
             active_conditions_count: 0,
+            total_keeper_rewards: 0,
         });
 
         log!(&env, "Smart Swap contract initialized with admin: {}", admin);
         Ok(())
     }
 
+    /// Whether `initialize` has run. Calls that need the config fail with `not_initialized` until then.
+    pub fn is_initialized(env: Env) -> bool {
+        env.storage().instance().has(&DataKey::Admin)
+    }
+
     pub fn create_swap_condition(
         env: Env,
         caller: Address,
         request: CreateSwapRequest,
     ) -> Result<u64, Symbol> {
         caller.require_auth();
-        Self::check_not_paused(&env)?;
+        Self::check_not_paused(&env, PausableOperation::Create)?;
 
         // Validate the request
-        request.validate(&env)?;
+        request.validate(&env).map_err(|error| error.message)?;
 
         let config: ContractConfig = env
             .storage()
@@ -109,80 +259,126 @@ impl SmartSwap {
             .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
 
         // Check user condition limit
-        Self::check_user_condition_limit(&env, &caller, config.max_conditions_per_user)?;
+        Self::check_user_condition_limit(&env, &caller, config.max_conditions_per_user, 1)?;
+        Self::check_global_condition_limit(&env, &config, 1)?;
 
-        // Validate minimum value
-        if request.amount_to_swap < config.min_condition_value {
-            return Err(Symbol::new(&env, "amount_below_minimum"));
-        }
+        let current_price = Self::check_creation_request(&env, &config, &request)?;
+        Self::store_new_condition(&env, &config, &caller, request, &current_price)
+    }
 
-        // Get current price from oracle
-        let price_result = PriceOracleClient::get_price(
-            &env,
-            &config.oracle_config,
-            request.source_asset.clone(),
-        );
+    /// Create a grid of conditions in one call. Every request is checked before any is stored
+    /// and the per-user limit applies to the whole batch, so a bad request creates nothing.
+    pub fn create_swap_conditions(
+        env: Env,
+        caller: Address,
+        requests: Vec<CreateSwapRequest>,
+    ) -> Result<Vec<u64>, Symbol> {
+        caller.require_auth();
+        Self::check_not_paused(&env, PausableOperation::Create)?;
 
-        if !price_result.success {
-            return Err(price_result.error_message.unwrap_or(Symbol::new(&env, "price_unavailable")));
+        if requests.is_empty() || requests.len() > MAX_BATCH_CREATE_SIZE {
+            return Err(Symbol::new(&env, "invalid_batch_size"));
         }
 
-        let current_price = price_result.price_data.ok_or_else(|| Symbol::new(&env, "no_price_data"))?;
-
-        // Validate price data for swap
-        PriceOracleClient::validate_price_for_swap(&env, &current_price, &config.oracle_config)?;
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
 
-        // Check DEX liquidity
-        let has_liquidity = StellarDexIntegration::check_liquidity(
-            &env,
-            &config.dex_config,
-            request.source_asset.clone(),
-            request.destination_asset.clone(),
-            request.amount_to_swap,
-        )?;
+        Self::check_user_condition_limit(&env, &caller, config.max_conditions_per_user, requests.len())?;
+        Self::check_global_condition_limit(&env, &config, requests.len())?;
+
+        // The volume cap has to hold for the batch as a whole, not just each request alone
+        let mut batch_volume: Map<Symbol, u64> = Map::new(&env);
+        let mut prices = Vec::new(&env);
+        for request in requests.iter() {
+            request.validate(&env).map_err(|error| error.message)?;
+            prices.push_back(Self::check_creation_request(&env, &config, &request)?);
+
+            let asset_volume = batch_volume
+                .get(request.source_asset.clone())
+                .unwrap_or(0)
+                .saturating_add(request.amount_to_swap);
+            if config.max_pending_per_asset > 0 {
+                let pending = Self::get_pending_volume(env.clone(), request.source_asset.clone());
+                if pending.saturating_add(asset_volume) > config.max_pending_per_asset {
+                    return Err(Symbol::new(&env, "volume_cap_exceeded"));
+                }
+            }
+            batch_volume.set(request.source_asset.clone(), asset_volume);
+        }
 
-        if !has_liquidity {
-            return Err(Symbol::new(&env, "insufficient_liquidity"));
+        let mut condition_ids = Vec::new(&env);
+        for (request, current_price) in requests.iter().zip(prices.iter()) {
+            condition_ids.push_back(Self::store_new_condition(&env, &config, &caller, request, &current_price)?);
         }
 
-        // Generate condition ID and create condition
-        let condition_id = Self::get_next_condition_id(&env);
-        let swap_condition = SwapCondition::new(
-            &env,
-            condition_id,
-            caller.clone(),
-            request,
-            current_price.price,
-        );
+        Ok(condition_ids)
+    }
 
-        // Store the condition
-        let mut conditions: Map<u64, SwapCondition> = env
+    /// Run every creation-time check for `request` without storing anything or requiring auth,
+    /// so wallets can surface errors before asking the user to sign
+    pub fn validate_request(env: Env, request: CreateSwapRequest) -> Result<(), Symbol> {
+        Self::check_not_paused(&env, PausableOperation::Create)?;
+
+        request.validate(&env).map_err(|error| error.message)?;
+
+        let config: ContractConfig = env
             .storage()
             .instance()
-            .get(&DataKey::SwapConditions)
-            .unwrap_or_else(|| Map::new(&env));
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
 
-        conditions.set(condition_id, swap_condition);
-        env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+        Self::check_global_condition_limit(&env, &config, 1)?;
+        Self::check_creation_request(&env, &config, &request)?;
+        Ok(())
+    }
 
-        // Update user conditions
-        Self::add_user_condition(&env, &caller, condition_id);
+    /// Create a condition and escrow `amount_to_swap` of the source token in one call. Any
+    /// failure reverts the whole invocation, so no id is minted and no escrow is held.
+    pub fn create_funded_condition(
+        env: Env,
+        caller: Address,
+        request: CreateSwapRequest,
+    ) -> Result<u64, Symbol> {
+        caller.require_auth();
 
-        // Update global stats
-        Self::update_global_stats(&env, |stats| {
-            stats.total_conditions_created += 1;
-            stats.active_conditions_count += 1;
-        });
+        let token_address: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::AssetToken(request.source_asset.clone()))
+            .ok_or_else(|| Symbol::new(&env, "asset_token_not_set"))?;
+
+        // Check funding up front so a short balance fails before anything is written
+        let token_client = token::Client::new(&env, &token_address);
+        let amount = request.amount_to_swap as i128;
+        if token_client.balance(&caller) < amount {
+            return Err(Symbol::new(&env, "insufficient_balance"));
+        }
+
+        let condition_id = Self::create_swap_condition(env.clone(), caller.clone(), request)?;
+
+        token_client.transfer(&caller, &env.current_contract_address(), &amount);
+        env.storage()
+            .instance()
+            .set(&DataKey::ConditionEscrow(condition_id), &(token_address, amount));
 
-        log!(&env, "Swap condition created: {} for user: {}", condition_id, caller);
+        log!(&env, "Condition {} funded with {}", condition_id, amount);
         Ok(condition_id)
     }
 
+    /// Check a condition's trigger and execute it when met. `Ok(None)` means nothing executed on
+    /// this check: the trigger isn't met, the swap was blocked or failed and will be retried, or
+    /// the oracle price was unavailable or below the confidence floor. Those last two are deferred
+    /// to `get_retry_queue` rather than returned as errors, which would roll the queue entry back.
     pub fn check_and_execute_condition(
         env: Env,
+        keeper: Address,
         condition_id: u64,
     ) -> Result<Option<SwapExecution>, Symbol> {
-        Self::check_not_paused(&env)?;
+        keeper.require_auth();
+        Self::check_not_paused(&env, PausableOperation::Execute)?;
 
         let mut conditions: Map<u64, SwapCondition> = env
             .storage()
@@ -193,8 +389,21 @@ impl SmartSwap {
         let mut condition = conditions.get(&condition_id)
             .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
 
+        // Reject re-entry while this condition's swap is in flight
+        if condition.status == SwapStatus::Executing {
+            return Err(Symbol::new(&env, "reentrant_execution"));
+        }
+
         // Validate condition is still active
-        condition.is_valid(&env)?;
+        condition.is_valid(&env).map_err(|error| error.message)?;
+
+        if !condition.is_activated(env.ledger().timestamp()) {
+            return Err(Symbol::new(&env, "not_yet_active"));
+        }
+
+        if env.ledger().timestamp() < condition.next_execution_time() {
+            return Err(Symbol::new(&env, "execution_interval_not_elapsed"));
+        }
 
         let config: ContractConfig = env
             .storage()
@@ -202,6 +411,17 @@ impl SmartSwap {
             .get(&DataKey::Admin)
             .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
 
+        // Rate limit checks per condition before spending an oracle call
+        if config.min_check_interval_secs > 0
+            && env.ledger().timestamp() < condition.last_check.saturating_add(config.min_check_interval_secs)
+        {
+            return Err(Symbol::new(&env, "checked_too_soon"));
+        }
+
+        if condition.try_auto_renew(env.ledger().timestamp()) {
+            log!(&env, "Condition {} auto-renewed until {}", condition_id, condition.expires_at);
+        }
+
         // Get current price
         let price_result = PriceOracleClient::get_price(
             &env,
@@ -209,14 +429,41 @@ impl SmartSwap {
             condition.source_asset.clone(),
         );
 
-        if !price_result.success {
-            return Err(price_result.error_message.unwrap_or(Symbol::new(&env, "price_unavailable")));
+        // An oracle outage is transient. Queue the condition for a later re-check and
+        // return Ok so the queue write isn't rolled back with the invocation.
+        let current_price = match price_result.price_data {
+            Some(price_data) if price_result.success => price_data,
+            _ => {
+                Self::enqueue_retry(&env, condition_id);
+                log!(&env, "Condition {} queued for retry: {:?}", condition_id, price_result.error_message);
+                return Ok(None);
+            }
+        };
+
+        // A stricter per-condition confidence floor is treated like any other low-quality price
+        if condition.min_confidence_override.is_some() {
+            let mut price_rules = config.oracle_config.clone();
+            price_rules.min_confidence = condition.effective_min_confidence(config.oracle_config.min_confidence);
+            price_rules.min_fallback_confidence = price_rules.min_fallback_confidence.max(price_rules.min_confidence);
+            if let Err(error) = PriceOracleClient::validate_price_for_swap(&env, &current_price, &price_rules) {
+                Self::enqueue_retry(&env, condition_id);
+                log!(&env, "Condition {} queued for retry: {:?}", condition_id, error);
+                return Ok(None);
+            }
         }
+        Self::record_price_sample(&env, &config.oracle_config, &current_price);
 
-        let current_price = price_result.price_data.ok_or_else(|| Symbol::new(&env, "no_price_data"))?;
+        // Lets keepers tell a skip on stale or low-confidence data from a trigger that isn't met
+        env.events().publish(
+            ("condition_checked",),
+            (condition_id, current_price.timestamp, current_price.confidence),
+        );
+
+        condition.record_observation(env.ledger().timestamp(), current_price.price);
 
         // Check if condition should be executed
-        if !condition.should_execute(current_price.price) {
+        let trigger_value = Self::trigger_input(&env, &config, &condition, current_price.price)?;
+        if !condition.should_execute(trigger_value) {
             // Update last check time
             condition.last_check = env.ledger().timestamp();
             conditions.set(condition_id, condition);
@@ -224,12 +471,98 @@ impl SmartSwap {
             return Ok(None);
         }
 
-        // Execute the swap
-        let execution_result = Self::execute_swap(&env, &config, &condition, &current_price)?;
+        // Circuit breaker against extreme moves (e.g. an oracle bug), independent of the trigger.
+        // The error rolls the invocation back, so a halted check doesn't update `last_check`.
+        if condition.price_deviation_bps(current_price.price) > config.max_deviation_bps {
+            log!(&env, "Condition {} halted, price {} too far from reference {}", condition_id, current_price.price, condition.reference_price);
+            return Err(Symbol::new(&env, "price_deviation_halt"));
+        }
+
+        // Persist the Executing marker before calling out to the DEX, so a re-entrant call
+        // for the same condition is rejected instead of executing twice
+        condition.status = SwapStatus::Executing;
+        conditions.set(condition_id, condition.clone());
+        env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+        let swap_outcome = Self::execute_swap(&env, &config, &condition, &current_price);
+
+        // Reload after the external call so writes made meanwhile aren't clobbered
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .ok_or_else(|| Symbol::new(&env, "no_conditions"))?;
+        condition.status = SwapStatus::Active;
+
+        // A trigger blocked only by slippage, price impact, reference price protection or oracle/DEX
+        // divergence isn't a failure, the condition stays active until the market improves.
+        let mut execution_result = match swap_outcome {
+            Ok(execution) => execution,
+            Err(error)
+                if error == Symbol::new(&env, "slippage_exceeded")
+                    || error == Symbol::new(&env, "price_impact_too_high")
+                    || error == Symbol::new(&env, "below_reference_price")
+                    || error == Symbol::new(&env, "oracle_dex_divergence") =>
+            {
+                condition.last_check = env.ledger().timestamp();
+                conditions.set(condition_id, condition);
+                env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+                Self::enqueue_retry(&env, condition_id);
+                log!(&env, "Condition {} triggered but blocked by {}", condition_id, error);
+                return Ok(None);
+            }
+            // Other failures count against the condition's retries, except ones no retry can fix
+            Err(error) => {
+                condition.last_check = env.ledger().timestamp();
+                if Self::is_permanent_failure(&env, &error) {
+                    condition.mark_as_failed();
+                } else {
+                    condition.record_retryable_failure();
+                }
+
+                if condition.status == SwapStatus::Failed {
+                    Self::adjust_pending_volume(&env, &condition.source_asset, condition.amount_remaining, 0);
+                    Self::dequeue_retry(&env, condition_id);
+                    Self::refund_escrow(&env, condition_id, &condition.owner);
+                    Self::update_global_stats(&env, |stats| {
+                        stats.active_conditions_count = stats.active_conditions_count.saturating_sub(1);
+                    });
+                    env.events().publish(("execution_failed",), (condition_id, error.clone()));
+                    log!(&env, "Condition {} failed: {}", condition_id, error);
+                } else {
+                    Self::enqueue_retry(&env, condition_id);
+                    env.events().publish(("execution_retry",), (condition_id, condition.retry_count, error.clone()));
+                    log!(&env, "Condition {} execution failed, retry {}: {}", condition_id, condition.retry_count, error);
+                }
+
+                conditions.set(condition_id, condition);
+                env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+                return Ok(None);
+            }
+        };
 
         if execution_result.success {
+            // Reward the keeper only on genuine execution
+            let keeper_reward = Self::calculate_bps_share(execution_result.amount_in, config.keeper_reward_bps);
+            execution_result.keeper_reward = keeper_reward;
+            let protocol_fee_bps = Self::effective_fee_bps(&env, &config, &condition.owner);
+            let protocol_fee = Self::calculate_bps_share(execution_result.amount_in, protocol_fee_bps);
+            execution_result.protocol_fee = protocol_fee;
+
             // Update condition with execution info
+            let remaining_before = condition.amount_remaining;
             condition.update_execution(&env, &execution_result);
+            let remaining_after = if condition.status == SwapStatus::Active {
+                condition.amount_remaining
+            } else {
+                0
+            };
+            Self::adjust_pending_volume(&env, &condition.source_asset, remaining_before, remaining_after);
+
+            // The swap is simulated and never draws on the escrow, so it goes back whole once the condition is done
+            if condition.status == SwapStatus::Executed {
+                Self::refund_escrow(&env, condition_id, &condition.owner);
+            }
             
             // Store execution record
             Self::store_execution_record(&env, condition_id, execution_result.clone());
@@ -238,17 +571,29 @@ impl SmartSwap {
             Self::update_global_stats(&env, |stats| {
                 stats.total_conditions_executed += 1;
                 stats.total_volume_swapped += execution_result.amount_in;
+                stats.total_keeper_rewards += keeper_reward;
+                stats.total_fees_collected += protocol_fee;
                 if condition.status == SwapStatus::Executed {
                     stats.active_conditions_count = stats.active_conditions_count.saturating_sub(1);
                 }
             });
 
-            log!(&env, "Condition {} executed successfully", condition_id);
+            Self::record_asset_volume(&env, &condition.source_asset, execution_result.amount_in);
+            Self::record_user_volume(&env, &condition.owner, execution_result.amount_in);
+            Self::accrue_keeper_reward(&env, &config, &keeper, &condition.source_asset, keeper_reward);
+            Self::accrue_protocol_fee(&env, protocol_fee);
+
+            log!(&env, "Condition {} executed successfully, keeper {} rewarded {}", condition_id, keeper, keeper_reward);
         } else {
             condition.mark_as_failed();
+            Self::adjust_pending_volume(&env, &condition.source_asset, condition.amount_remaining, 0);
+            Self::refund_escrow(&env, condition_id, &condition.owner);
             log!(&env, "Condition {} execution failed: {:?}", condition_id, execution_result.error_message);
         }
 
+        // Either outcome is final for this attempt, nothing left to retry
+        Self::dequeue_retry(&env, condition_id);
+
         // Update condition status
         conditions.set(condition_id, condition);
         env.storage().instance().set(&DataKey::SwapConditions, &conditions);
@@ -256,10 +601,95 @@ impl SmartSwap {
         Ok(Some(execution_result))
     }
 
-    pub fn cancel_condition(
+    /// Slippage tolerance in basis points the condition would need for the current DEX quote to
+    /// clear its minimum output. Only meaningful while the trigger is met.
+    pub fn required_slippage_for_now(env: Env, condition_id: u64) -> Result<u32, Symbol> {
+        let condition = Self::get_condition(env.clone(), condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        let price_result = PriceOracleClient::get_price(
+            &env,
+            &config.oracle_config,
+            condition.source_asset.clone(),
+        );
+        let current_price = match price_result.price_data {
+            Some(price_data) if price_result.success => price_data.price,
+            _ => return Err(price_result.error_message.unwrap_or(Symbol::new(&env, "price_unavailable"))),
+        };
+
+        let trigger_value = Self::trigger_input(&env, &config, &condition, current_price)?;
+        if !condition.should_execute(trigger_value) {
+            return Err(Symbol::new(&env, "trigger_not_met"));
+        }
+
+        let quote = StellarDexIntegration::get_swap_quote(
+            &env,
+            &config.dex_config,
+            condition.source_asset.clone(),
+            condition.destination_asset.clone(),
+            condition.next_fill_amount(),
+        )?;
+
+        Ok(condition.required_slippage_bps(current_price, quote.amount_out))
+    }
+
+    /// Project what executing a condition right now would produce, without checking its trigger
+    /// or changing any state
+    pub fn simulate_execution(env: Env, condition_id: u64) -> Result<SwapExecution, Symbol> {
+        let condition = Self::get_condition(env.clone(), condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        let price_result = PriceOracleClient::get_price(
+            &env,
+            &config.oracle_config,
+            condition.source_asset.clone(),
+        );
+        let current_price = match price_result.price_data {
+            Some(price_data) if price_result.success => price_data,
+            _ => return Err(price_result.error_message.unwrap_or(Symbol::new(&env, "price_unavailable"))),
+        };
+
+        let swap_params = Self::build_swap_params(&env, &config, &condition, current_price.price);
+        let quote = Self::execution_quote(&env, &config, &condition, &swap_params)?;
+
+        let mut execution = SwapExecution::new(
+            &env,
+            condition_id,
+            current_price.price,
+            quote.amount_in,
+            quote.amount_out,
+            StellarDexIntegration::projected_gas_used(&quote),
+            Symbol::new(&env, "simulation"),
+        );
+        execution.crossing_price = condition.crossing_price(current_price.price);
+        execution.price_impact = quote.price_impact;
+        execution.keeper_reward = Self::calculate_bps_share(quote.amount_in, config.keeper_reward_bps);
+        let protocol_fee_bps = Self::effective_fee_bps(&env, &config, &condition.owner);
+        execution.protocol_fee = Self::calculate_bps_share(quote.amount_in, protocol_fee_bps);
+
+        Ok(execution)
+    }
+
+    /// Push back the expiry of an active condition, keeping its reference price and history.
+    /// The new expiry must be later than the current one and within the maximum lifetime
+    /// counted from creation.
+    pub fn extend_expiry(
         env: Env,
         caller: Address,
         condition_id: u64,
+        new_expires_at: u64,
     ) -> Result<(), Symbol> {
         caller.require_auth();
 
@@ -269,95 +699,1570 @@ impl SmartSwap {
             .get(&DataKey::SwapConditions)
             .ok_or_else(|| Symbol::new(&env, "no_conditions"))?;
 
-        let mut condition = conditions.get(&condition_id)
+        let mut condition = conditions.get(condition_id)
             .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
 
-        // Check ownership
         if condition.owner != caller {
             return Err(Symbol::new(&env, "not_owner"));
         }
 
-        // Check if condition can be cancelled
-        match condition.status {
-            SwapStatus::Active => {
-                condition.cancel();
-                conditions.set(condition_id, condition);
-                env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+        if condition.status != SwapStatus::Active {
+            return Err(Symbol::new(&env, "cannot_extend"));
+        }
 
-                // Update global stats
-                Self::update_global_stats(&env, |stats| {
-                    stats.active_conditions_count = stats.active_conditions_count.saturating_sub(1);
-                });
+        if new_expires_at <= env.ledger().timestamp() || new_expires_at <= condition.expires_at {
+            return Err(Symbol::new(&env, "invalid_expiry"));
+        }
 
-                log!(&env, "Condition {} cancelled by user", condition_id);
-                Ok(())
-            }
-            _ => Err(Symbol::new(&env, "cannot_cancel")),
+        if new_expires_at > condition.created_at.saturating_add(MAX_CONDITION_LIFETIME) {
+            return Err(Symbol::new(&env, "lifetime_too_long"));
         }
-    }
 
-    pub fn get_condition(env: Env, condition_id: u64) -> Option<SwapCondition> {
-        let conditions: Map<u64, SwapCondition> = env
-            .storage()
-            .instance()
-            .get(&DataKey::SwapConditions)
-            .unwrap_or_else(|| Map::new(&env));
+        condition.expires_at = new_expires_at;
+        conditions.set(condition_id, condition);
+        env.storage().instance().set(&DataKey::SwapConditions, &conditions);
 
-        conditions.get(&condition_id)
+        env.events().publish(("expiry_extended",), (condition_id, new_expires_at));
+        log!(&env, "Condition {} extended until {}", condition_id, new_expires_at);
+        Ok(())
     }
 
-    pub fn get_user_conditions(env: Env, user: Address) -> Vec<u64> {
-        env.storage()
-            .instance()
-            .get(&DataKey::UserConditions(user))
-            .unwrap_or_else(|| Vec::new(&env))
-    }
+    /// Set the owner's memo on a condition. Symbols cap the memo at 32 characters, an empty
+    /// memo clears it.
+    pub fn set_condition_memo(
+        env: Env,
+        caller: Address,
+        condition_id: u64,
+        memo: Symbol,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
 
-    pub fn get_condition_executions(env: Env, condition_id: u64) -> Vec<SwapExecution> {
-        let executions: Map<u64, Vec<SwapExecution>> = env
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .ok_or_else(|| Symbol::new(&env, "no_conditions"))?;
+
+        let mut condition = conditions.get(condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+
+        if condition.owner != caller {
+            return Err(Symbol::new(&env, "not_owner"));
+        }
+
+        condition.memo = if memo == Symbol::new(&env, "") {
+            None
+        } else {
+            Some(memo)
+        };
+        conditions.set(condition_id, condition);
+        env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+        log!(&env, "Memo updated for condition {}", condition_id);
+        Ok(())
+    }
+
+    /// Trim a condition's execution history to the most recent `keep_recent` records (owner only)
+    pub fn prune_executions(
+        env: Env,
+        caller: Address,
+        condition_id: u64,
+        keep_recent: u32,
+    ) -> Result<u32, Symbol> {
+        caller.require_auth();
+
+        let owner = Self::get_condition_owner(env.clone(), condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+        if owner != caller {
+            return Err(Symbol::new(&env, "not_owner"));
+        }
+
+        let mut executions: Map<u64, Vec<SwapExecution>> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapExecutions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let condition_executions = executions
+            .get(condition_id)
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let total = condition_executions.len();
+        if total <= keep_recent {
+            return Ok(0);
+        }
+
+        let removed = total - keep_recent;
+        executions.set(condition_id, condition_executions.slice(removed..total));
+        env.storage().instance().set(&DataKey::SwapExecutions, &executions);
+
+        log!(&env, "Pruned {} execution records for condition {}", removed, condition_id);
+        Ok(removed)
+    }
+
+    /// Modify an active condition in place. Assets are fixed since the reference price depends on them.
+    pub fn update_condition(
+        env: Env,
+        caller: Address,
+        condition_id: u64,
+        new_slippage: u32,
+        new_expires_at: u64,
+        new_condition_type: SwapConditionType,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_not_paused(&env, PausableOperation::Create)?;
+
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .ok_or_else(|| Symbol::new(&env, "no_conditions"))?;
+
+        let mut condition = conditions.get(condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+
+        if condition.owner != caller {
+            return Err(Symbol::new(&env, "not_owner"));
+        }
+
+        if condition.status != SwapStatus::Active {
+            return Err(Symbol::new(&env, "cannot_update"));
+        }
+
+        // Re-validate with the same rules as creation
+        let mut request = condition.to_request(env.ledger().timestamp());
+        request.max_slippage = new_slippage;
+        request.expires_at = new_expires_at;
+        request.condition_type = new_condition_type.clone();
+        request.validate(&env).map_err(|error| error.message)?;
+
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+        if new_slippage > config.dex_config.max_slippage_tolerance {
+            return Err(Symbol::new(&env, "slippage_above_dex_tolerance"));
+        }
+
+        condition.apply_update(&env, new_slippage, new_expires_at, new_condition_type)?;
+        Self::check_trigger_within_deviation(&env, &config, &condition)?;
+        conditions.set(condition_id, condition);
+        env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+        env.events().publish(("condition_updated",), (condition_id, caller));
+        log!(&env, "Condition {} updated by owner", condition_id);
+        Ok(())
+    }
+
+    pub fn cancel_condition(
+        env: Env,
+        caller: Address,
+        condition_id: u64,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_not_paused(&env, PausableOperation::Cancel)?;
+
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .ok_or_else(|| Symbol::new(&env, "no_conditions"))?;
+
+        let mut condition = conditions.get(&condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+
+        // Check ownership
+        if condition.owner != caller {
+            return Err(Symbol::new(&env, "not_owner"));
+        }
+
+        // Check if condition can be cancelled
+        match condition.status {
+            SwapStatus::Active => {
+                condition.cancel();
+                Self::adjust_pending_volume(&env, &condition.source_asset, condition.amount_remaining, 0);
+                conditions.set(condition_id, condition);
+                env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+                Self::refund_escrow(&env, condition_id, &caller);
+                Self::dequeue_retry(&env, condition_id);
+
+                // Update global stats
+                Self::update_global_stats(&env, |stats| {
+                    stats.active_conditions_count = stats.active_conditions_count.saturating_sub(1);
+                });
+
+                log!(&env, "Condition {} cancelled by user", condition_id);
+                Ok(())
+            }
+            _ => Err(Symbol::new(&env, "cannot_cancel")),
+        }
+    }
+
+    /// Cancel every active condition owned by the caller, returning how many were cancelled.
+    /// Only the caller's own index is walked; conditions in any other state are skipped.
+    pub fn cancel_all_conditions(env: Env, caller: Address) -> Result<u32, Symbol> {
+        caller.require_auth();
+        Self::check_not_paused(&env, PausableOperation::Cancel)?;
+
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut cancelled_count = 0u32;
+        for condition_id in Self::get_user_conditions(env.clone(), caller.clone()).iter() {
+            let mut condition = match conditions.get(condition_id) {
+                Some(condition) if condition.owner == caller && condition.status == SwapStatus::Active => condition,
+                _ => continue,
+            };
+
+            condition.cancel();
+            Self::adjust_pending_volume(&env, &condition.source_asset, condition.amount_remaining, 0);
+            conditions.set(condition_id, condition);
+            Self::refund_escrow(&env, condition_id, &caller);
+            Self::dequeue_retry(&env, condition_id);
+            cancelled_count += 1;
+        }
+
+        if cancelled_count > 0 {
+            env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+            Self::update_global_stats(&env, |stats| {
+                stats.active_conditions_count = stats.active_conditions_count.saturating_sub(cancelled_count as u64);
+            });
+            log!(&env, "Cancelled {} conditions for user: {}", cancelled_count, caller);
+        }
+
+        Ok(cancelled_count)
+    }
+
+    /// Incident response escape hatch: cancel any active condition regardless of owner.
+    /// Escrowed funds go back to the owner and the condition leaves their index.
+    pub fn admin_cancel_condition(
+        env: Env,
+        caller: Address,
+        condition_id: u64,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .ok_or_else(|| Symbol::new(&env, "no_conditions"))?;
+
+        let mut condition = conditions.get(condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+
+        if condition.status != SwapStatus::Active {
+            return Err(Symbol::new(&env, "cannot_cancel"));
+        }
+
+        let owner = condition.owner.clone();
+        condition.cancel();
+        Self::adjust_pending_volume(&env, &condition.source_asset, condition.amount_remaining, 0);
+        conditions.set(condition_id, condition);
+        env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+        Self::refund_escrow(&env, condition_id, &owner);
+        Self::remove_user_condition(&env, &owner, condition_id);
+        Self::dequeue_retry(&env, condition_id);
+
+        Self::update_global_stats(&env, |stats| {
+            stats.active_conditions_count = stats.active_conditions_count.saturating_sub(1);
+        });
+
+        env.events().publish(("admin_cancel",), (condition_id, owner));
+        log!(&env, "Condition {} cancelled by admin", condition_id);
+        Ok(())
+    }
+
+    pub fn transfer_condition(
+        env: Env,
+        caller: Address,
+        condition_id: u64,
+        new_owner: Address,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_not_paused(&env, PausableOperation::Create)?;
+
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .ok_or_else(|| Symbol::new(&env, "no_conditions"))?;
+
+        let mut condition = conditions.get(condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+
+        if condition.owner != caller {
+            return Err(Symbol::new(&env, "not_owner"));
+        }
+
+        if condition.status != SwapStatus::Active {
+            return Err(Symbol::new(&env, "cannot_transfer"));
+        }
+
+        if new_owner == caller {
+            return Err(Symbol::new(&env, "same_owner"));
+        }
+
+        // Refunds go to the owner, so handing over a funded condition would hand over the funder's tokens
+        if env.storage().instance().has(&DataKey::ConditionEscrow(condition_id)) {
+            return Err(Symbol::new(&env, "condition_funded"));
+        }
+
+        // The condition counts against the recipient's limit from now on
+        Self::check_user_condition_limit(&env, &new_owner, config.max_conditions_per_user, 1)?;
+
+        condition.owner = new_owner.clone();
+        conditions.set(condition_id, condition);
+        env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+        env.storage().instance().set(&DataKey::ConditionOwner(condition_id), &new_owner);
+
+        Self::remove_user_condition(&env, &caller, condition_id);
+        Self::add_user_condition(&env, &new_owner, condition_id);
+
+        env.events().publish(("condition_transferred",), (condition_id, caller, new_owner));
+        log!(&env, "Condition {} transferred to new owner", condition_id);
+        Ok(())
+    }
+
+    pub fn refresh_user_min_outputs(env: Env, caller: Address) -> Result<u32, Symbol> {
+        caller.require_auth();
+        Self::check_not_paused(&env, PausableOperation::Create)?;
+
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let user_conditions = Self::get_user_conditions(env.clone(), caller.clone());
+        let mut updated_count = 0u32;
+
+        for condition_id in user_conditions.iter() {
+            let mut condition = match conditions.get(condition_id) {
+                Some(condition) => condition,
+                None => continue,
+            };
+
+            if condition.owner != caller || condition.status != SwapStatus::Active {
+                continue;
+            }
+
+            let price_result = PriceOracleClient::get_price(
+                &env,
+                &config.oracle_config,
+                condition.source_asset.clone(),
+            );
+
+            if !price_result.success {
+                return Err(price_result.error_message.unwrap_or(Symbol::new(&env, "price_unavailable")));
+            }
+
+            let current_price = price_result.price_data.ok_or_else(|| Symbol::new(&env, "no_price_data"))?;
+
+            condition.refresh_min_amount_out(current_price.price);
+            conditions.set(condition_id, condition);
+            updated_count += 1;
+        }
+
+        if updated_count > 0 {
+            env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+            log!(&env, "Refreshed min outputs for {} conditions of user: {}", updated_count, caller);
+        }
+
+        Ok(updated_count)
+    }
+
+    pub fn compact_user_conditions(env: Env, user: Address) -> Result<u32, Symbol> {
+        user.require_auth();
+
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let user_conditions = Self::get_user_conditions(env.clone(), user.clone());
+        let mut retained = Vec::new(&env);
+
+        // Keep only conditions that can still execute, dropping dangling IDs too
+        for condition_id in user_conditions.iter() {
+            if let Some(condition) = conditions.get(condition_id) {
+                if condition.status == SwapStatus::Active {
+                    retained.push_back(condition_id);
+                }
+            }
+        }
+
+        let removed_count = user_conditions.len() - retained.len();
+        if removed_count > 0 {
+            env.storage().instance().set(&DataKey::UserConditions(user.clone()), &retained);
+            log!(&env, "Compacted {} conditions from index of user: {}", removed_count, user);
+        }
+
+        Ok(removed_count)
+    }
+
+    pub fn get_condition(env: Env, condition_id: u64) -> Option<SwapCondition> {
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        conditions.get(&condition_id)
+    }
+
+    /// Get up to `limit` condition IDs waiting for a re-check after a transient failure
+    pub fn get_retry_queue(env: Env, limit: u32) -> Vec<u64> {
+        let queue: Vec<u64> = env
+            .storage()
+            .instance()
+            .get(&DataKey::RetryQueue)
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut result = Vec::new(&env);
+        for condition_id in queue.iter().take(limit as usize) {
+            result.push_back(condition_id);
+        }
+        result
+    }
+
+    pub fn get_condition_owner(env: Env, condition_id: u64) -> Option<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::ConditionOwner(condition_id))
+    }
+
+    /// Get up to `limit` active condition IDs whose trigger is met at the current oracle price,
+    /// highest execution priority first
+    pub fn get_executable_conditions(env: Env, limit: u32) -> Vec<u64> {
+        let mut result = Vec::new(&env);
+        if limit == 0 {
+            return result;
+        }
+
+        let config: ContractConfig = match env.storage().instance().get(&DataKey::Admin) {
+            Some(config) => config,
+            None => return result,
+        };
+
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        // Price each source asset once, None when the oracle can't serve it
+        let mut price_cache: Map<Symbol, Option<u64>> = Map::new(&env);
+
+        // Best `limit` (priority, id) pairs so far, highest first. Equal priorities keep scan order.
+        let mut ranked: Vec<(u64, u64)> = Vec::new(&env);
+
+        for (condition_id, condition) in conditions.iter() {
+            if condition.status != SwapStatus::Active
+                || condition.is_valid(&env).is_err()
+                || env.ledger().timestamp() < condition.next_execution_time()
+            {
+                continue;
+            }
+
+            let price = match price_cache.get(condition.source_asset.clone()) {
+                Some(cached) => cached,
+                None => {
+                    let price_result = PriceOracleClient::get_price(
+                        &env,
+                        &config.oracle_config,
+                        condition.source_asset.clone(),
+                    );
+                    let price = match price_result.price_data {
+                        Some(price_data) if price_result.success => Some(price_data.price),
+                        _ => None,
+                    };
+                    price_cache.set(condition.source_asset.clone(), price);
+                    price
+                }
+            };
+
+            let trigger_value = price.and_then(|price| Self::trigger_input(&env, &config, &condition, price).ok());
+            if let Some(trigger_value) = trigger_value {
+                if condition.should_execute(trigger_value) {
+                    let priority = condition.execution_priority(trigger_value);
+                    let mut position = ranked.len();
+                    while position > 0 && ranked.get(position - 1).unwrap().0 < priority {
+                        position -= 1;
+                    }
+                    if position < limit {
+                        ranked.insert(position, (priority, condition_id));
+                        if ranked.len() > limit {
+                            ranked.pop_back();
+                        }
+                    }
+                }
+            }
+        }
+
+        for (_, condition_id) in ranked.iter() {
+            result.push_back(condition_id);
+        }
+        result
+    }
+
+    /// Active condition on `asset` whose trigger price is the smallest move away from the live price
+    pub fn get_closest_to_trigger(env: Env, asset: Symbol) -> Option<u64> {
+        let config: ContractConfig = env.storage().instance().get(&DataKey::Admin)?;
+
+        let price_result = PriceOracleClient::get_price(&env, &config.oracle_config, asset.clone());
+        let current_price = match price_result.price_data {
+            Some(price_data) if price_result.success => price_data.price,
+            _ => return None,
+        };
+
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut closest: Option<(u64, u64)> = None;
+        for (condition_id, condition) in conditions.iter() {
+            if condition.status != SwapStatus::Active || condition.source_asset != asset {
+                continue;
+            }
+
+            // Rate conditions have no trigger in source price units
+            let trigger_price = match condition.trigger_price() {
+                Some(trigger_price) => trigger_price,
+                None => continue,
+            };
+
+            let distance = current_price.abs_diff(trigger_price);
+            if closest.map_or(true, |(_, closest_distance)| distance < closest_distance) {
+                closest = Some((condition_id, distance));
+            }
+        }
+
+        closest.map(|(condition_id, _)| condition_id)
+    }
+
+    /// Rolling window of prices observed for `asset`, oldest first, capped at `twap_max_samples`
+    pub fn get_price_history(env: Env, asset: Symbol) -> Vec<PriceData> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PriceHistory(asset))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Time-weighted average of the prices in `get_price_history` for `asset`
+    pub fn get_price_twap(env: Env, asset: Symbol) -> Result<u64, Symbol> {
+        PriceOracleClient::calculate_twap(&env, &Self::get_price_history(env.clone(), asset))
+    }
+
+    /// Fills left for a recurring condition, None when `max_executions` is unlimited. See
+    /// `get_condition_liveness` for when the next one may happen.
+    pub fn get_remaining_executions(env: Env, condition_id: u64) -> Result<Option<u32>, Symbol> {
+        let condition = Self::get_condition(env.clone(), condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+        Ok(condition.remaining_executions())
+    }
+
+    pub fn get_condition_liveness(env: Env, condition_id: u64) -> Result<Liveness, Symbol> {
+        let condition = Self::get_condition(env.clone(), condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+
+        let current_time = env.ledger().timestamp();
+        let is_active = condition.status == SwapStatus::Active;
+        let is_expired = condition.status == SwapStatus::Expired || current_time > condition.expires_at;
+        let is_valid = condition.is_valid(&env).is_ok();
+
+        let next_eligible_time = if is_active && is_valid {
+            Some(current_time.max(condition.next_execution_time()))
+        } else {
+            None
+        };
+
+        let is_executable_now = is_valid
+            && current_time >= condition.next_execution_time()
+            && Self::check_not_paused(&env, PausableOperation::Execute).is_ok()
+            && Self::is_trigger_met(&env, &condition);
+
+        Ok(Liveness {
+            is_active,
+            is_expired,
+            is_executable_now,
+            next_eligible_time,
+        })
+    }
+
+    /// Stored status alongside whether the condition would fire at the live price. Missing
+    /// conditions and oracle failures are reported in `error` rather than failing the call.
+    pub fn get_condition_status(env: Env, condition_id: u64) -> ConditionStatusReport {
+        let mut report = ConditionStatusReport {
+            status: None,
+            current_price: None,
+            price_timestamp: None,
+            confidence: None,
+            would_execute: false,
+            projected_output: 0,
+            error: None,
+        };
+
+        let condition = match Self::get_condition(env.clone(), condition_id) {
+            Some(condition) => condition,
+            None => {
+                report.error = Some(Symbol::new(&env, "condition_not_found"));
+                return report;
+            }
+        };
+        report.status = Some(condition.status.clone());
+
+        let config: ContractConfig = match env.storage().instance().get(&DataKey::Admin) {
+            Some(config) => config,
+            None => {
+                report.error = Some(Symbol::new(&env, "not_initialized"));
+                return report;
+            }
+        };
+
+        let price_result = PriceOracleClient::get_price(&env, &config.oracle_config, condition.source_asset.clone());
+        let current_price = match price_result.price_data {
+            Some(price_data) if price_result.success => {
+                report.price_timestamp = Some(price_data.timestamp);
+                report.confidence = Some(price_data.confidence);
+                price_data.price
+            }
+            _ => {
+                report.error = Some(price_result.error_message.unwrap_or(Symbol::new(&env, "price_unavailable")));
+                return report;
+            }
+        };
+        report.current_price = Some(current_price);
+
+        match Self::trigger_input(&env, &config, &condition, current_price) {
+            Ok(trigger_value) => {
+                report.would_execute = condition.status == SwapStatus::Active && condition.should_execute(trigger_value);
+            }
+            Err(error) => report.error = Some(error),
+        }
+
+        // Nothing left to fill once a condition is done
+        if condition.next_fill_amount() == 0 {
+            return report;
+        }
+
+        match StellarDexIntegration::get_swap_quote(
+            &env,
+            &config.dex_config,
+            condition.source_asset.clone(),
+            condition.destination_asset.clone(),
+            condition.next_fill_amount(),
+        ) {
+            Ok(quote) => report.projected_output = quote.amount_out,
+            Err(error) => report.error = Some(error),
+        }
+
+        report
+    }
+
+    pub fn get_user_conditions(env: Env, user: Address) -> Vec<u64> {
+        env.storage()
+            .instance()
+            .get(&DataKey::UserConditions(user))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    pub fn get_user_conditions_by_status(
+        env: Env,
+        user: Address,
+        status: SwapStatus,
+    ) -> Vec<SwapCondition> {
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut matching = Vec::new(&env);
+        for condition_id in Self::get_user_conditions(env.clone(), user).iter() {
+            // Skip dangling IDs whose condition is no longer stored
+            if let Some(condition) = conditions.get(condition_id) {
+                if condition.status == status {
+                    matching.push_back(condition);
+                }
+            }
+        }
+
+        matching
+    }
+
+    /// IDs of the user's conditions carrying `label`, in any status
+    pub fn get_user_conditions_by_label(env: Env, user: Address, label: Symbol) -> Vec<u64> {
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut matching = Vec::new(&env);
+        for condition_id in Self::get_user_conditions(env.clone(), user).iter() {
+            if let Some(condition) = conditions.get(condition_id) {
+                if condition.label.as_ref() == Some(&label) {
+                    matching.push_back(condition_id);
+                }
+            }
+        }
+
+        matching
+    }
+
+    /// Group the user's active conditions by time left until expiry. Conditions already past
+    /// expiry but not yet cleaned up are left out.
+    pub fn get_conditions_by_expiry_bucket(env: Env, user: Address) -> ExpiryBuckets {
+        let current_time = env.ledger().timestamp();
+        let mut buckets = ExpiryBuckets {
+            within_day: Vec::new(&env),
+            within_week: Vec::new(&env),
+            later: Vec::new(&env),
+        };
+
+        for condition in Self::get_user_conditions_by_status(env.clone(), user, SwapStatus::Active).iter() {
+            if current_time > condition.expires_at {
+                continue;
+            }
+
+            let time_left = condition.expires_at - current_time;
+            if time_left < EXPIRY_BUCKET_DAY_SECS {
+                buckets.within_day.push_back(condition.id);
+            } else if time_left < EXPIRY_BUCKET_WEEK_SECS {
+                buckets.within_week.push_back(condition.id);
+            } else {
+                buckets.later.push_back(condition.id);
+            }
+        }
+
+        buckets
+    }
+
+    /// Advisory: pairs of the user's active conditions trading the same pair in opposite
+    /// directions, which could undo each other's fills
+    pub fn detect_conflicts(env: Env, user: Address) -> Vec<(u64, u64)> {
+        let active = Self::get_user_conditions_by_status(env.clone(), user, SwapStatus::Active);
+
+        let mut conflicts = Vec::new(&env);
+        for (index, condition) in active.iter().enumerate() {
+            for other in active.iter().skip(index + 1) {
+                if condition.conflicts_with(&other) {
+                    conflicts.push_back((condition.id.min(other.id), condition.id.max(other.id)));
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    pub fn get_user_assets(env: Env, user: Address) -> Vec<Symbol> {
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut assets = Vec::new(&env);
+        for condition_id in Self::get_user_conditions(env.clone(), user).iter() {
+            if let Some(condition) = conditions.get(condition_id) {
+                if condition.status != SwapStatus::Active {
+                    continue;
+                }
+
+                if !assets.contains(&condition.source_asset) {
+                    assets.push_back(condition.source_asset.clone());
+                }
+                if !assets.contains(&condition.destination_asset) {
+                    assets.push_back(condition.destination_asset.clone());
+                }
+            }
+        }
+
+        assets
+    }
+
+    /// Page through executions across all conditions, newest first. `start` counts back from
+    /// the most recent execution and `limit` is capped at MAX_EXECUTION_PAGE_SIZE.
+    pub fn get_recent_executions(env: Env, start: u32, limit: u32) -> Vec<SwapExecution> {
+        let log_length: u32 = env.storage().instance().get(&DataKey::ExecutionLogLength).unwrap_or(0);
+
+        let mut page = Vec::new(&env);
+        let end = start.saturating_add(limit.min(MAX_EXECUTION_PAGE_SIZE)).min(log_length);
+        for offset in start..end {
+            let index = log_length - 1 - offset;
+            if let Some(execution) = env.storage().persistent().get(&DataKey::ExecutionLog(index)) {
+                page.push_back(execution);
+            }
+        }
+
+        page
+    }
+
+    /// Page through every condition by ID. The page covers IDs `start` to `start + limit - 1`
+    /// (IDs begin at 1), so IDs no longer stored leave the page short rather than shifting
+    /// later pages. `limit` is capped at MAX_CONDITION_PAGE_SIZE.
+    pub fn get_all_conditions_paged(env: Env, start: u32, limit: u32) -> Vec<SwapCondition> {
+        let next_id: u64 = env.storage().instance().get(&DataKey::NextConditionId).unwrap_or(1);
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut page = Vec::new(&env);
+        let first = (start as u64).max(1);
+        let end = first.saturating_add(limit.min(MAX_CONDITION_PAGE_SIZE) as u64).min(next_id);
+        for condition_id in first..end {
+            if let Some(condition) = conditions.get(condition_id) {
+                page.push_back(condition);
+            }
+        }
+
+        page
+    }
+
+    pub fn get_condition_executions(env: Env, condition_id: u64) -> Vec<SwapExecution> {
+        let executions: Map<u64, Vec<SwapExecution>> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapExecutions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        executions.get(&condition_id).unwrap_or_else(|| Vec::new(&env))
+    }
+
+    pub fn get_swap_quote(
+        env: Env,
+        token_in: Symbol,
+        token_out: Symbol,
+        amount_in: u64,
+    ) -> Result<SwapQuote, Symbol> {
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        Self::check_supported_assets(&env, &config, &token_in, &token_out)?;
+
+        StellarDexIntegration::get_swap_quote(&env, &config.dex_config, token_in, token_out, amount_in)
+    }
+
+    pub fn estimate_output_at_trigger(env: Env, condition_id: u64) -> Result<u64, Symbol> {
+        let condition = Self::get_condition(env.clone(), condition_id)
+            .ok_or_else(|| Symbol::new(&env, "condition_not_found"))?;
+
+        let trigger_price = condition
+            .trigger_price()
+            .ok_or_else(|| Symbol::new(&env, "no_trigger_price"))?;
+
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        let price_result = PriceOracleClient::get_price(
+            &env,
+            &config.oracle_config,
+            condition.source_asset.clone(),
+        );
+
+        if !price_result.success {
+            return Err(price_result.error_message.unwrap_or(Symbol::new(&env, "price_unavailable")));
+        }
+
+        let current_price = price_result.price_data.ok_or_else(|| Symbol::new(&env, "no_price_data"))?;
+        if current_price.price == 0 {
+            return Err(Symbol::new(&env, "zero_price"));
+        }
+
+        // Current pool output, scaled by how far the source price moves to reach the trigger
+        let quote = StellarDexIntegration::get_swap_quote(
+            &env,
+            &config.dex_config,
+            condition.source_asset,
+            condition.destination_asset,
+            condition.amount_to_swap,
+        )?;
+
+        let estimate = (quote.amount_out as u128 * trigger_price as u128) / current_price.price as u128;
+        Ok(estimate.min(u64::MAX as u128) as u64)
+    }
+
+    pub fn get_spot_price(env: Env, token_in: Symbol, token_out: Symbol) -> Result<u64, Symbol> {
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        Self::check_supported_assets(&env, &config, &token_in, &token_out)?;
+
+        StellarDexIntegration::get_spot_price(&env, &config.dex_config, token_in, token_out)
+    }
+
+    pub fn get_pool_info(env: Env, token_a: Symbol, token_b: Symbol) -> Result<PoolInfo, Symbol> {
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        Self::check_supported_assets(&env, &config, &token_a, &token_b)?;
+
+        StellarDexIntegration::get_pool_info(&env, &config.dex_config, token_a, token_b)
+    }
+
+    pub fn get_oracle_dex_spread(
+        env: Env,
+        token_in: Symbol,
+        token_out: Symbol,
+        amount_in: u64,
+    ) -> Result<i32, Symbol> {
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        let oracle_output = PriceOracleClient::estimate_swap_output(
+            &env,
+            &config.oracle_config,
+            token_in.clone(),
+            token_out.clone(),
+            amount_in,
+            Self::get_asset_decimals(env.clone(), token_in.clone()),
+            Self::get_asset_decimals(env.clone(), token_out.clone()),
+        )?;
+
+        if oracle_output == 0 {
+            return Err(Symbol::new(&env, "zero_oracle_output"));
+        }
+
+        let quote = StellarDexIntegration::get_swap_quote(&env, &config.dex_config, token_in, token_out, amount_in)?;
+
+        // Positive when the DEX gives more than the oracle implies
+        let spread_bps = ((quote.amount_out as i128 - oracle_output as i128) * 10000) / oracle_output as i128;
+        Ok(spread_bps.clamp(i32::MIN as i128, i32::MAX as i128) as i32)
+    }
+
+    pub fn estimate_condition_cost(
+        env: Env,
+        request: CreateSwapRequest,
+    ) -> Result<ConditionCostEstimate, Symbol> {
+        request.validate(&env).map_err(|error| error.message)?;
+
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        let quote = StellarDexIntegration::get_swap_quote(
+            &env,
+            &config.dex_config,
+            request.source_asset,
+            request.destination_asset,
+            request.amount_to_swap,
+        )?;
+
+        let lifetime = request.expires_at.saturating_sub(env.ledger().timestamp());
+        Ok(SwapConditionManager::estimate_cost(lifetime, quote.estimated_gas))
+    }
+
+    pub fn add_supported_asset(
+        env: Env,
+        caller: Address,
+        asset_symbol: Symbol,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        let mut supported_assets: Vec<Symbol> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SupportedAssets)
+            .unwrap_or_else(|| Vec::new(&env));
+
+        if !supported_assets.iter().any(|asset| asset == asset_symbol) {
+            supported_assets.push_back(asset_symbol.clone());
+            env.storage().instance().set(&DataKey::SupportedAssets, &supported_assets);
+        }
+
+        log!(&env, "Asset added to supported list: {}", asset_symbol);
+        Ok(())
+    }
+
+    /// Remove an asset from the supported list. Removing an asset that isn't listed is a no-op.
+    pub fn remove_supported_asset(
+        env: Env,
+        caller: Address,
+        asset_symbol: Symbol,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        let mut supported_assets = Self::get_supported_assets(env.clone());
+        if let Some(index) = supported_assets.first_index_of(&asset_symbol) {
+            supported_assets.remove(index);
+            env.storage().instance().set(&DataKey::SupportedAssets, &supported_assets);
+            log!(&env, "Asset removed from supported list: {}", asset_symbol);
+        }
+
+        Ok(())
+    }
+
+    pub fn get_supported_assets(env: Env) -> Vec<Symbol> {
+        env.storage()
+            .instance()
+            .get(&DataKey::SupportedAssets)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    pub fn set_asset_token(
+        env: Env,
+        caller: Address,
+        asset_symbol: Symbol,
+        token_address: Address,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        env.storage()
+            .instance()
+            .set(&DataKey::AssetToken(asset_symbol.clone()), &token_address);
+
+        log!(&env, "Token set for asset {}: {}", asset_symbol, token_address);
+        Ok(())
+    }
+
+    /// Decimals used to convert `asset` amounts between base units and whole units
+    pub fn set_asset_decimals(
+        env: Env,
+        caller: Address,
+        asset_symbol: Symbol,
+        decimals: u32,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if decimals > MAX_ASSET_DECIMALS {
+            return Err(Symbol::new(&env, "invalid_decimals"));
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::AssetMeta(asset_symbol.clone()), &AssetMeta { decimals });
+
+        log!(&env, "Decimals set for asset {}: {}", asset_symbol, decimals);
+        Ok(())
+    }
+
+    /// Decimals registered for `asset`, `DEFAULT_ASSET_DECIMALS` when unset
+    pub fn get_asset_decimals(env: Env, asset_symbol: Symbol) -> u32 {
+        env.storage()
+            .instance()
+            .get::<DataKey, AssetMeta>(&DataKey::AssetMeta(asset_symbol))
+            .map(|meta| meta.decimals)
+            .unwrap_or(DEFAULT_ASSET_DECIMALS)
+    }
+
+    pub fn get_condition_escrow(env: Env, condition_id: u64) -> i128 {
+        env.storage()
+            .instance()
+            .get::<DataKey, (Address, i128)>(&DataKey::ConditionEscrow(condition_id))
+            .map(|(_, amount)| amount)
+            .unwrap_or(0)
+    }
+
+    pub fn set_asset_whitelist_enforcement(
+        env: Env,
+        caller: Address,
+        enabled: bool,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        let mut config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        config.enforce_asset_whitelist = enabled;
+        env.storage().instance().set(&DataKey::Admin, &config);
+
+        log!(&env, "Asset whitelist enforcement set to: {}", enabled);
+        Ok(())
+    }
+
+    /// Pay keeper rewards in `asset`, whose token contract is `token_address`. Rewards accrue in
+    /// this asset's units from then on, so the token can't change while the pool holds funds.
+    pub fn set_reward_token(
+        env: Env,
+        caller: Address,
+        asset: Symbol,
+        token_address: Address,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if Self::get_keeper_reward_pool(env.clone()) > 0 {
+            return Err(Symbol::new(&env, "reward_pool_not_empty"));
+        }
+
+        env.storage().instance().set(&DataKey::RewardToken, &(asset.clone(), token_address.clone()));
+
+        log!(&env, "Keeper reward token set to: {} ({})", asset, token_address);
+        Ok(())
+    }
+
+    /// Move `amount` of the reward token from the admin into the pool keeper claims are paid from
+    pub fn fund_keeper_rewards(env: Env, caller: Address, amount: u64) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if amount == 0 {
+            return Err(Symbol::new(&env, "zero_amount"));
+        }
+
+        let (_, token_address): (Symbol, Address) = env
+            .storage()
+            .instance()
+            .get(&DataKey::RewardToken)
+            .ok_or_else(|| Symbol::new(&env, "reward_token_not_set"))?;
+
+        token::Client::new(&env, &token_address).transfer(&caller, &env.current_contract_address(), &(amount as i128));
+        let pool = Self::get_keeper_reward_pool(env.clone());
+        env.storage().instance().set(&DataKey::KeeperRewardPool, &pool.saturating_add(amount));
+
+        env.events().publish(("keeper_rewards_funded",), amount);
+        log!(&env, "Keeper reward pool funded with {}", amount);
+        Ok(())
+    }
+
+    pub fn get_keeper_reward_pool(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::KeeperRewardPool)
+            .unwrap_or(0)
+    }
+
+    pub fn get_keeper_rewards(env: Env, keeper: Address) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::KeeperRewards(keeper))
+            .unwrap_or(0)
+    }
+
+    /// Pay out the caller's accrued keeper rewards to `to` from the funded reward pool
+    pub fn claim_keeper_rewards(env: Env, caller: Address, to: Address) -> Result<u64, Symbol> {
+        caller.require_auth();
+
+        let (_, token_address): (Symbol, Address) = env
+            .storage()
+            .instance()
+            .get(&DataKey::RewardToken)
+            .ok_or_else(|| Symbol::new(&env, "reward_token_not_set"))?;
+
+        let rewards = Self::get_keeper_rewards(env.clone(), caller.clone());
+        if rewards == 0 {
+            return Err(Symbol::new(&env, "no_rewards"));
+        }
+
+        // Only the pool is paid out, never tokens the contract holds in escrow
+        let pool = Self::get_keeper_reward_pool(env.clone());
+        if rewards > pool {
+            return Err(Symbol::new(&env, "insufficient_reward_pool"));
+        }
+
+        // Zero the balance before paying out
+        env.storage().instance().remove(&DataKey::KeeperRewards(caller.clone()));
+        env.storage().instance().set(&DataKey::KeeperRewardPool, &(pool - rewards));
+        token::Client::new(&env, &token_address).transfer(&env.current_contract_address(), &to, &(rewards as i128));
+
+        log!(&env, "Keeper {} claimed {} rewards", caller, rewards);
+        Ok(rewards)
+    }
+
+    pub fn get_collected_fees(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::CollectedFees)
+            .unwrap_or(0)
+    }
+
+    /// Move `amount` of the collected protocol fees to `to`, e.g. a treasury
+    pub fn withdraw_fees(env: Env, caller: Address, to: Address, amount: u64) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if amount == 0 {
+            return Err(Symbol::new(&env, "zero_amount"));
+        }
+
+        let collected = Self::get_collected_fees(env.clone());
+        if amount > collected {
+            return Err(Symbol::new(&env, "insufficient_fees"));
+        }
+
+        // Fees are tracked as a balance only, the simulated swaps don't hold the fee tokens
+        env.storage().instance().set(&DataKey::CollectedFees, &(collected - amount));
+
+        env.events().publish(("fees_withdrawn",), (to.clone(), amount));
+        log!(&env, "Withdrew {} protocol fees to {}", amount, to);
+        Ok(())
+    }
+
+    /// Reset `reference_price` to the current oracle price for the listed active percentage
+    /// conditions, e.g. after an oracle outage. Returns how many were updated.
+    pub fn admin_refresh_references(
+        env: Env,
+        caller: Address,
+        condition_ids: Vec<u64>,
+    ) -> Result<u32, Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut updated = 0u32;
+        for condition_id in condition_ids.iter() {
+            let mut condition = match conditions.get(condition_id) {
+                Some(condition) => condition,
+                None => continue,
+            };
+
+            if condition.status != SwapStatus::Active || !condition.is_percentage_based() {
+                continue;
+            }
+
+            let price_result = PriceOracleClient::get_price(
+                &env,
+                &config.oracle_config,
+                condition.source_asset.clone(),
+            );
+            match price_result.price_data {
+                Some(price_data) if price_result.success => {
+                    condition.reference_price = price_data.price;
+                    conditions.set(condition_id, condition);
+                    updated += 1;
+                }
+                _ => continue,
+            }
+        }
+
+        if updated > 0 {
+            env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+        }
+
+        log!(&env, "Refreshed reference prices for {} conditions", updated);
+        Ok(updated)
+    }
+
+    pub fn set_max_pending_per_asset(
+        env: Env,
+        caller: Address,
+        max_pending_per_asset: u64,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        let mut config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        config.max_pending_per_asset = max_pending_per_asset;
+        env.storage().instance().set(&DataKey::Admin, &config);
+
+        log!(&env, "Max pending volume per asset set to: {}", max_pending_per_asset);
+        Ok(())
+    }
+
+    /// Minimum seconds between checks of the same condition. Creation counts as a check.
+    pub fn set_min_check_interval(
+        env: Env,
+        caller: Address,
+        min_check_interval_secs: u64,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if min_check_interval_secs > MAX_MIN_CHECK_INTERVAL_SECS {
+            return Err(Symbol::new(&env, "invalid_check_interval"));
+        }
+
+        let mut config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        config.min_check_interval_secs = min_check_interval_secs;
+        env.storage().instance().set(&DataKey::Admin, &config);
+
+        log!(&env, "Min check interval set to: {} seconds", min_check_interval_secs);
+        Ok(())
+    }
+
+    /// Bound on how far the DEX rate may sit from the oracle price at execution, 0 disables the check
+    pub fn set_max_oracle_dex_divergence(
+        env: Env,
+        caller: Address,
+        max_divergence_bps: u32,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        let mut config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        config.max_oracle_dex_divergence_bps = max_divergence_bps;
+        env.storage().instance().set(&DataKey::Admin, &config);
+
+        log!(&env, "Max oracle/DEX divergence set to: {} bps", max_divergence_bps);
+        Ok(())
+    }
+
+    /// Protocol-wide ceiling on active conditions, independent of the per-user limit
+    pub fn set_max_total_active_conditions(
+        env: Env,
+        caller: Address,
+        max_total_active_conditions: u64,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if max_total_active_conditions == 0 {
+            return Err(Symbol::new(&env, "invalid_condition_cap"));
+        }
+
+        let mut config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        config.max_total_active_conditions = max_total_active_conditions;
+        env.storage().instance().set(&DataKey::Admin, &config);
+
+        log!(&env, "Max total active conditions set to: {}", max_total_active_conditions);
+        Ok(())
+    }
+
+    /// Get the unfilled amount across active conditions selling `asset`
+    pub fn get_pending_volume(env: Env, asset: Symbol) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::PendingVolume(asset))
+            .unwrap_or(0)
+    }
+
+    pub fn set_protocol_fee(
+        env: Env,
+        caller: Address,
+        protocol_fee_bps: u32,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if protocol_fee_bps > MAX_PROTOCOL_FEE_BPS {
+            return Err(Symbol::new(&env, "invalid_protocol_fee"));
+        }
+
+        let mut config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        config.protocol_fee_bps = protocol_fee_bps;
+        env.storage().instance().set(&DataKey::Admin, &config);
+
+        log!(&env, "Protocol fee set to: {} bps", protocol_fee_bps);
+        Ok(())
+    }
+
+    /// Replace the volume discount schedule (admin only). Tiers must be in ascending volume
+    /// order and can only lower the protocol fee. An empty schedule removes the discounts.
+    pub fn set_fee_tiers(env: Env, caller: Address, tiers: Vec<FeeTier>) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if tiers.len() > MAX_FEE_TIERS {
+            return Err(Symbol::new(&env, "too_many_fee_tiers"));
+        }
+
+        let mut previous: Option<FeeTier> = None;
+        for tier in tiers.iter() {
+            if tier.fee_bps > MAX_PROTOCOL_FEE_BPS {
+                return Err(Symbol::new(&env, "invalid_protocol_fee"));
+            }
+            if let Some(previous) = previous {
+                if tier.min_volume <= previous.min_volume || tier.fee_bps > previous.fee_bps {
+                    return Err(Symbol::new(&env, "invalid_fee_tiers"));
+                }
+            }
+            previous = Some(tier);
+        }
+
+        env.storage().instance().set(&DataKey::FeeTiers, &tiers);
+        log!(&env, "Fee tier schedule set with {} tiers", tiers.len());
+        Ok(())
+    }
+
+    pub fn get_fee_tiers(env: Env) -> Vec<FeeTier> {
+        env.storage()
+            .instance()
+            .get(&DataKey::FeeTiers)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Cumulative amount the user's conditions have executed
+    pub fn get_user_volume(env: Env, user: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::UserVolume(user))
+            .unwrap_or(0)
+    }
+
+    /// Highest fee tier the user's volume has reached, None when they pay the base fee
+    pub fn get_user_fee_tier(env: Env, user: Address) -> Option<FeeTier> {
+        let volume = Self::get_user_volume(env.clone(), user);
+        let mut reached = None;
+        for tier in Self::get_fee_tiers(env).iter() {
+            if volume >= tier.min_volume {
+                reached = Some(tier);
+            }
+        }
+        reached
+    }
+
+    /// Sum protocol fees from executions with `from_ts <= executed_at <= to_ts` (admin only).
+    /// Scans at most MAX_FEE_REVENUE_SCAN records and errors rather than returning a partial sum.
+    pub fn get_fee_revenue_in_range(
+        env: Env,
+        caller: Address,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> Result<u64, Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if from_ts > to_ts {
+            return Err(Symbol::new(&env, "invalid_time_range"));
+        }
+
+        let executions: Map<u64, Vec<SwapExecution>> = env
             .storage()
             .instance()
             .get(&DataKey::SwapExecutions)
             .unwrap_or_else(|| Map::new(&env));
 
-        executions.get(&condition_id).unwrap_or_else(|| Vec::new(&env))
+        let mut scanned = 0u32;
+        let mut revenue = 0u64;
+        for (_, condition_executions) in executions.iter() {
+            scanned = scanned.saturating_add(condition_executions.len());
+            if scanned > MAX_FEE_REVENUE_SCAN {
+                return Err(Symbol::new(&env, "scan_limit_exceeded"));
+            }
+
+            for execution in condition_executions.iter() {
+                if execution.executed_at >= from_ts && execution.executed_at <= to_ts {
+                    revenue = revenue.saturating_add(execution.protocol_fee);
+                }
+            }
+        }
+
+        Ok(revenue)
     }
 
-    pub fn get_swap_quote(
+    pub fn set_max_deviation(
         env: Env,
-        token_in: Symbol,
-        token_out: Symbol,
-        amount_in: u64,
-    ) -> Result<SwapQuote, Symbol> {
-        let config: ContractConfig = env
+        caller: Address,
+        max_deviation_bps: u32,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        if max_deviation_bps == 0 {
+            return Err(Symbol::new(&env, "invalid_max_deviation"));
+        }
+
+        let mut config: ContractConfig = env
             .storage()
             .instance()
             .get(&DataKey::Admin)
             .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
 
-        StellarDexIntegration::get_swap_quote(&env, &config.dex_config, token_in, token_out, amount_in)
+        config.max_deviation_bps = max_deviation_bps;
+        env.storage().instance().set(&DataKey::Admin, &config);
+
+        log!(&env, "Max price deviation set to: {} bps", max_deviation_bps);
+        Ok(())
     }
 
-    pub fn add_supported_asset(
+    pub fn set_swap_deadline(
         env: Env,
         caller: Address,
-        asset_symbol: Symbol,
+        deadline_secs: u64,
     ) -> Result<(), Symbol> {
         caller.require_auth();
         Self::check_admin(&env, &caller)?;
 
-        let mut supported_assets: Vec<Symbol> = env
+        if deadline_secs < MIN_SWAP_DEADLINE_SECS || deadline_secs > MAX_SWAP_DEADLINE_SECS {
+            return Err(Symbol::new(&env, "invalid_swap_deadline"));
+        }
+
+        let mut config: ContractConfig = env
             .storage()
             .instance()
-            .get(&DataKey::SupportedAssets)
-            .unwrap_or_else(|| Vec::new(&env));
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
 
-        if !supported_assets.iter().any(|asset| asset == asset_symbol) {
-            supported_assets.push_back(asset_symbol.clone());
-            env.storage().instance().set(&DataKey::SupportedAssets, &supported_assets);
-        }
+        config.swap_deadline_secs = deadline_secs;
+        env.storage().instance().set(&DataKey::Admin, &config);
 
-        log!(&env, "Asset added to supported list: {}", asset_symbol);
+        log!(&env, "Swap deadline set to: {} seconds", deadline_secs);
         Ok(())
     }
 
@@ -369,23 +2274,100 @@ impl SmartSwap {
         caller.require_auth();
         Self::check_admin(&env, &caller)?;
 
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        Self::store_pause_flags(&env, config, PauseFlags::all(paused));
+
+        log!(&env, "Contract pause status set to: {}", paused);
+        Ok(())
+    }
+
+    pub fn set_pause_flags(
+        env: Env,
+        caller: Address,
+        pause_flags: PauseFlags,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        let config: ContractConfig = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
+
+        Self::store_pause_flags(&env, config, pause_flags.clone());
+
+        log!(&env, "Pause flags set to: {:?}", pause_flags);
+        Ok(())
+    }
+
+    pub fn get_pause_flags(env: Env) -> PauseFlags {
+        env.storage()
+            .instance()
+            .get(&DataKey::PausedStatus)
+            .unwrap_or(PauseFlags::all(false))
+    }
+
+    pub fn is_paused(env: Env) -> bool {
+        Self::get_pause_flags(env).any()
+    }
+
+    pub fn propose_admin(
+        env: Env,
+        caller: Address,
+        new_admin: Address,
+    ) -> Result<(), Symbol> {
+        caller.require_auth();
+        Self::check_admin(&env, &caller)?;
+
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
+        env.events().publish(("admin_proposed",), (caller, new_admin.clone()));
+
+        log!(&env, "Admin transfer proposed to: {}", new_admin);
+        Ok(())
+    }
+
+    pub fn accept_admin(env: Env, caller: Address) -> Result<(), Symbol> {
+        caller.require_auth();
+
+        let pending_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .ok_or_else(|| Symbol::new(&env, "no_pending_admin"))?;
+
+        if caller != pending_admin {
+            return Err(Symbol::new(&env, "not_pending_admin"));
+        }
+
         let mut config: ContractConfig = env
             .storage()
             .instance()
             .get(&DataKey::Admin)
             .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
 
-        config.paused = paused;
+        let previous_admin = config.admin.clone();
+        config.admin = caller.clone();
         env.storage().instance().set(&DataKey::Admin, &config);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        env.events().publish(("admin_accepted",), (previous_admin, caller.clone()));
 
-        log!(&env, "Contract pause status set to: {}", paused);
+        log!(&env, "Admin transfer accepted by: {}", caller);
         Ok(())
     }
 
+    /// Replace the oracle config. The new oracle must serve a price for the native asset
+    /// unless `force` is set, so a bad address can't silently break every check.
     pub fn update_oracle_config(
         env: Env,
         caller: Address,
         new_oracle_config: OracleConfig,
+        force: bool,
     ) -> Result<(), Symbol> {
         caller.require_auth();
         Self::check_admin(&env, &caller)?;
@@ -393,6 +2375,14 @@ impl SmartSwap {
         // Validate new config
         OracleConfigManager::validate_config(&env, &new_oracle_config)?;
 
+        if !force {
+            let probe = PriceOracleClient::get_price(&env, &new_oracle_config, Symbol::new(&env, NATIVE_ASSET_SYMBOL));
+            if !probe.success {
+                log!(&env, "Oracle probe failed: {:?}", probe.error_message);
+                return Err(Symbol::new(&env, "oracle_probe_failed"));
+            }
+        }
+
         let mut config: ContractConfig = env
             .storage()
             .instance()
@@ -423,49 +2413,134 @@ impl SmartSwap {
             .get(&DataKey::Admin)
             .ok_or_else(|| Symbol::new(&env, "not_initialized"))?;
 
-        config.dex_config = new_dex_config;
-        env.storage().instance().set(&DataKey::Admin, &config);
+        config.dex_config = new_dex_config;
+        env.storage().instance().set(&DataKey::Admin, &config);
+
+        log!(&env, "DEX configuration updated");
+        Ok(())
+    }
+
+    /// Get the total amount swapped from `asset`, in that asset's own units
+    pub fn get_asset_volume(env: Env, asset: Symbol) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::AssetVolume(asset))
+            .unwrap_or(0)
+    }
+
+    pub fn get_global_stats(env: Env) -> GlobalStats {
+        env.storage()
+            .instance()
+            .get(&DataKey::GlobalStats)
+            .unwrap_or(GlobalStats {
+                total_conditions_created: 0,
+                total_conditions_executed: 0,
+                total_volume_swapped: 0,
+                total_fees_collected: 0,
+                active_conditions_count: 0,
+                total_keeper_rewards: 0,
+            })
+    }
+
+    /// Recompute tracked state from the conditions map and report any drift. Read-only, for monitoring.
+    pub fn verify_invariants(env: Env) -> InvariantReport {
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut actual_active_count = 0u64;
+        let mut owners: Vec<Address> = Vec::new(&env);
+        for (_, condition) in conditions.iter() {
+            if condition.status == SwapStatus::Active {
+                actual_active_count += 1;
+            }
+            if !owners.contains(&condition.owner) {
+                owners.push_back(condition.owner.clone());
+            }
+        }
+
+        let mut orphaned_user_entries = Vec::new(&env);
+        for owner in owners.iter() {
+            for condition_id in Self::get_user_conditions(env.clone(), owner.clone()).iter() {
+                let owned = conditions
+                    .get(condition_id)
+                    .map_or(false, |condition| condition.owner == owner);
+                if !owned {
+                    orphaned_user_entries.push_back(condition_id);
+                }
+            }
+        }
+
+        let stored_active_count = Self::get_global_stats(env.clone()).active_conditions_count;
+        InvariantReport {
+            stored_active_count,
+            actual_active_count,
+            active_count_matches: stored_active_count == actual_active_count,
+            orphaned_user_entries,
+        }
+    }
+
+    pub fn get_status_counts(env: Env) -> StatusCounts {
+        let conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut counts = StatusCounts {
+            active: 0,
+            executed: 0,
+            cancelled: 0,
+            failed: 0,
+            expired: 0,
+        };
+
+        for (_, condition) in conditions.iter() {
+            match condition.status {
+                SwapStatus::Active | SwapStatus::Executing => counts.active += 1,
+                SwapStatus::Executed => counts.executed += 1,
+                SwapStatus::Cancelled => counts.cancelled += 1,
+                SwapStatus::Failed => counts.failed += 1,
+                SwapStatus::Expired => counts.expired += 1,
+            }
+        }
 
-        log!(&env, "DEX configuration updated");
-        Ok(())
+        counts
     }
 
-    pub fn get_global_stats(env: Env) -> GlobalStats {
-        env.storage()
-            .instance()
-            .get(&DataKey::GlobalStats)
-            .unwrap_or(GlobalStats {
-                total_conditions_created: 0,
-                total_conditions_executed: 0,
-                total_volume_swapped: 0,
-                total_fees_collected: 0,
-                active_conditions_count: 0,
-            })
+    pub fn cleanup_expired_conditions(env: Env, limit: u32) -> u32 {
+        Self::cleanup_expired_detailed(env, limit).len()
     }
 
-    pub fn cleanup_expired_conditions(env: Env, limit: u32) -> u32 {
+    pub fn cleanup_expired_detailed(env: Env, limit: u32) -> Vec<u64> {
         let mut conditions: Map<u64, SwapCondition> = env
             .storage()
             .instance()
             .get(&DataKey::SwapConditions)
             .unwrap_or_else(|| Map::new(&env));
 
-        let mut cleaned_count = 0u32;
+        let mut expired_ids = Vec::new(&env);
         let current_time = env.ledger().timestamp();
 
         // Iterate through conditions and mark expired ones
         for (condition_id, mut condition) in conditions.iter() {
-            if cleaned_count >= limit {
+            if expired_ids.len() >= limit {
                 break;
             }
 
             if current_time > condition.expires_at && condition.status == SwapStatus::Active {
                 condition.mark_as_expired(&env);
+                Self::adjust_pending_volume(&env, &condition.source_asset, condition.amount_remaining, 0);
+                Self::refund_escrow(&env, condition_id, &condition.owner);
+                Self::dequeue_retry(&env, condition_id);
                 conditions.set(condition_id, condition);
-                cleaned_count += 1;
+                expired_ids.push_back(condition_id);
             }
         }
 
+        let cleaned_count = expired_ids.len();
         if cleaned_count > 0 {
             env.storage().instance().set(&DataKey::SwapConditions, &conditions);
             
@@ -477,7 +2552,7 @@ impl SmartSwap {
             log!(&env, "Cleaned up {} expired conditions", cleaned_count);
         }
 
-        cleaned_count
+        expired_ids
     }
 
     // Internal helper methods
@@ -488,20 +2563,54 @@ impl SmartSwap {
         current_price: &PriceData,
     ) -> Result<SwapExecution, Symbol> {
         // Create swap parameters
-        let swap_params = SwapParams {
-            token_in: condition.source_asset.clone(),
-            token_out: condition.destination_asset.clone(),
-            amount_in: condition.amount_to_swap,
-            amount_out_min: condition.min_amount_out,
-            to: condition.owner.clone(),
-            deadline: env.ledger().timestamp() + 300, // 5 minutes deadline
-        };
+        let swap_params = Self::build_swap_params(env, config, condition, current_price.price);
+
+        // Pool depth can drop after creation
+        let has_liquidity = StellarDexIntegration::check_liquidity(
+            env,
+            &config.dex_config,
+            swap_params.token_in.clone(),
+            swap_params.token_out.clone(),
+            swap_params.amount_in,
+        )?;
+        if !has_liquidity {
+            return Err(Symbol::new(env, "insufficient_liquidity"));
+        }
+
+        StellarDexIntegration::validate_swap_execution(env, &swap_params)?;
+        let quote = Self::execution_quote(env, config, condition, &swap_params)?;
+
+        let check_divergence = config.max_oracle_dex_divergence_bps > 0;
+        let check_reference = condition.enforce_reference_price && condition.is_sell_higher();
+        if check_divergence || check_reference {
+            let oracle_output = PriceOracleClient::estimate_swap_output(
+                env,
+                &config.oracle_config,
+                swap_params.token_in.clone(),
+                swap_params.token_out.clone(),
+                swap_params.amount_in,
+                Self::get_asset_decimals(env.clone(), swap_params.token_in.clone()),
+                Self::get_asset_decimals(env.clone(), swap_params.token_out.clone()),
+            )?;
+
+            // The oracle fired the trigger, a DEX rate far from it means a desynced oracle or a sandwiched pool
+            if check_divergence
+                && Self::divergence_bps(quote.amount_out, oracle_output) > config.max_oracle_dex_divergence_bps
+            {
+                return Err(Symbol::new(env, "oracle_dex_divergence"));
+            }
+
+            // A thin pool can pass min_amount_out while still selling below what the user asked for
+            if check_reference && condition.fill_below_reference_price(current_price.price, quote.amount_out, oracle_output) {
+                return Err(Symbol::new(env, "below_reference_price"));
+            }
+        }
 
         // Execute swap through DEX integration
-        let swap_result = StellarDexIntegration::execute_swap(env, &config.dex_config, swap_params);
+        let swap_result = StellarDexIntegration::execute_swap_with_quote(env, &config.dex_config, swap_params, quote);
 
         // Create execution record
-        let execution = SwapExecution::new(
+        let mut execution = SwapExecution::new(
             env,
             condition.id,
             current_price.price,
@@ -510,6 +2619,8 @@ impl SmartSwap {
             swap_result.gas_used,
             swap_result.transaction_hash.clone(),
         );
+        execution.crossing_price = condition.crossing_price(current_price.price);
+        execution.price_impact = swap_result.actual_price_impact;
 
         if !swap_result.success {
             return Err(swap_result.error_message.unwrap_or(Symbol::new(env, "swap_failed")));
@@ -518,15 +2629,267 @@ impl SmartSwap {
         Ok(execution)
     }
 
-    fn get_next_condition_id(env: &Env) -> u64 {
+    /// DEX quote for the swap, with `amount_out` taken from the oracle rate for oracle-priced
+    /// conditions. Price impact, gas and validity still come from the DEX.
+    fn execution_quote(
+        env: &Env,
+        config: &ContractConfig,
+        condition: &SwapCondition,
+        swap_params: &SwapParams,
+    ) -> Result<SwapQuote, Symbol> {
+        let mut quote = StellarDexIntegration::get_swap_quote(
+            env,
+            &config.dex_config,
+            swap_params.token_in.clone(),
+            swap_params.token_out.clone(),
+            swap_params.amount_in,
+        )?;
+
+        if condition.execution_mode == ExecutionMode::OraclePriced {
+            quote.amount_out = PriceOracleClient::estimate_swap_output(
+                env,
+                &config.oracle_config,
+                swap_params.token_in.clone(),
+                swap_params.token_out.clone(),
+                swap_params.amount_in,
+                Self::get_asset_decimals(env.clone(), swap_params.token_in.clone()),
+                Self::get_asset_decimals(env.clone(), swap_params.token_out.clone()),
+            )?;
+        }
+
+        Ok(quote)
+    }
+
+    /// Failures caused by the swap itself being invalid, which no amount of retrying fixes
+    fn is_permanent_failure(env: &Env, error: &Symbol) -> bool {
+        *error == Symbol::new(env, "identical_tokens")
+            || *error == Symbol::new(env, "zero_amount")
+            || *error == Symbol::new(env, "amount_too_large")
+            || *error == Symbol::new(env, "invalid_min_output")
+    }
+
+    /// Store a condition that passed every creation check, priced at `current_price`
+    fn store_new_condition(
+        env: &Env,
+        config: &ContractConfig,
+        caller: &Address,
+        request: CreateSwapRequest,
+        current_price: &PriceData,
+    ) -> Result<u64, Symbol> {
+        Self::record_price_sample(env, &config.oracle_config, current_price);
+
+        // Generate condition ID and create condition
+        let condition_id = Self::get_next_condition_id(env)?;
+        let swap_condition = SwapCondition::new(
+            env,
+            condition_id,
+            caller.clone(),
+            request,
+            current_price.price,
+        )?;
+        Self::check_trigger_within_deviation(env, config, &swap_condition)?;
+        Self::adjust_pending_volume(env, &swap_condition.source_asset, 0, swap_condition.amount_remaining);
+
+        // Store the condition
+        let mut conditions: Map<u64, SwapCondition> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SwapConditions)
+            .unwrap_or_else(|| Map::new(env));
+
+        conditions.set(condition_id, swap_condition);
+        env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+        // Update user conditions
+        Self::add_user_condition(env, caller, condition_id);
+        env.storage().instance().set(&DataKey::ConditionOwner(condition_id), caller);
+
+        // Update global stats
+        Self::update_global_stats(env, |stats| {
+            stats.total_conditions_created += 1;
+            stats.active_conditions_count += 1;
+        });
+
+        log!(env, "Swap condition created: {} for user: {}", condition_id, caller);
+        Ok(condition_id)
+    }
+
+    /// Creation checks that depend on config, oracle and DEX state. Read-only, returns the
+    /// oracle price the condition would be created at.
+    /// A trigger further from the reference than the circuit breaker allows could never execute
+    fn check_trigger_within_deviation(env: &Env, config: &ContractConfig, condition: &SwapCondition) -> Result<(), Symbol> {
+        match condition.trigger_price() {
+            Some(trigger) if condition.price_deviation_bps(trigger) > config.max_deviation_bps => {
+                Err(Symbol::new(env, "trigger_beyond_deviation_bound"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_creation_request(
+        env: &Env,
+        config: &ContractConfig,
+        request: &CreateSwapRequest,
+    ) -> Result<PriceData, Symbol> {
+        // Validate minimum value
+        if request.amount_to_swap < config.min_condition_value {
+            return Err(Symbol::new(env, "amount_below_minimum"));
+        }
+
+        // A tolerance the DEX won't honour leaves a condition that can never execute
+        if request.max_slippage > config.dex_config.max_slippage_tolerance {
+            return Err(Symbol::new(env, "slippage_above_dex_tolerance"));
+        }
+
+        Self::check_supported_assets(env, config, &request.source_asset, &request.destination_asset)?;
+
+        // Cap outstanding exposure per source asset
+        if config.max_pending_per_asset > 0 {
+            let pending = Self::get_pending_volume(env.clone(), request.source_asset.clone());
+            if pending.saturating_add(request.amount_to_swap) > config.max_pending_per_asset {
+                return Err(Symbol::new(env, "volume_cap_exceeded"));
+            }
+        }
+
+        // Get current price from oracle
+        let price_result = PriceOracleClient::get_price(
+            env,
+            &config.oracle_config,
+            request.source_asset.clone(),
+        );
+
+        if !price_result.success {
+            return Err(price_result.error_message.unwrap_or(Symbol::new(env, "price_unavailable")));
+        }
+
+        let current_price = price_result.price_data.ok_or_else(|| Symbol::new(env, "no_price_data"))?;
+
+        // Validate price data for swap
+        PriceOracleClient::validate_price_for_swap(env, &current_price, &config.oracle_config)?;
+
+        // Check DEX liquidity
+        let has_liquidity = StellarDexIntegration::check_liquidity(
+            env,
+            &config.dex_config,
+            request.source_asset.clone(),
+            request.destination_asset.clone(),
+            request.amount_to_swap,
+        )?;
+
+        if !has_liquidity {
+            return Err(Symbol::new(env, "insufficient_liquidity"));
+        }
+
+        // Check price impact of the first fill
+        let first_fill = if request.chunk_size == 0 {
+            request.amount_to_swap
+        } else {
+            request.chunk_size
+        };
+        let quote = StellarDexIntegration::get_swap_quote(
+            env,
+            &config.dex_config,
+            request.source_asset.clone(),
+            request.destination_asset.clone(),
+            first_fill,
+        )?;
+
+        if quote.price_impact > request.max_price_impact_bps {
+            return Err(Symbol::new(env, "price_impact_too_high"));
+        }
+
+        Ok(current_price)
+    }
+
+    /// Value the trigger is evaluated against: the ledger timestamp for time conditions, the
+    /// source/destination exchange rate for rate conditions, otherwise the source price
+    fn trigger_input(
+        env: &Env,
+        config: &ContractConfig,
+        condition: &SwapCondition,
+        source_price: u64,
+    ) -> Result<u64, Symbol> {
+        if condition.is_time_based() {
+            return Ok(env.ledger().timestamp());
+        }
+
+        if !condition.uses_exchange_rate() {
+            return Ok(source_price);
+        }
+
+        PriceOracleClient::calculate_exchange_rate(
+            env,
+            &config.oracle_config,
+            condition.source_asset.clone(),
+            condition.destination_asset.clone(),
+            Self::get_asset_decimals(env.clone(), condition.source_asset.clone()),
+            Self::get_asset_decimals(env.clone(), condition.destination_asset.clone()),
+        )
+    }
+
+    fn is_trigger_met(env: &Env, condition: &SwapCondition) -> bool {
+        let config: ContractConfig = match env.storage().instance().get(&DataKey::Admin) {
+            Some(config) => config,
+            None => return false,
+        };
+
+        let price_result = PriceOracleClient::get_price(
+            env,
+            &config.oracle_config,
+            condition.source_asset.clone(),
+        );
+
+        match price_result.price_data {
+            Some(price_data) if price_result.success => {
+                match Self::trigger_input(env, &config, condition, price_data.price) {
+                    Ok(trigger_value) => condition.should_execute(trigger_value),
+                    Err(_) => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn calculate_bps_share(amount_in: u64, bps: u32) -> u64 {
+        ((amount_in as u128 * bps as u128) / 10000) as u64
+    }
+
+    /// Distance of the DEX output from the oracle estimate, in bps of the estimate
+    fn divergence_bps(dex_output: u64, oracle_output: u64) -> u32 {
+        if oracle_output == 0 {
+            return u32::MAX;
+        }
+        let diff = dex_output.abs_diff(oracle_output) as u128;
+        (diff * 10000 / oracle_output as u128).min(u32::MAX as u128) as u32
+    }
+
+    fn build_swap_params(
+        env: &Env,
+        config: &ContractConfig,
+        condition: &SwapCondition,
+        current_price: u64,
+    ) -> SwapParams {
+        SwapParams {
+            token_in: condition.source_asset.clone(),
+            token_out: condition.destination_asset.clone(),
+            amount_in: condition.next_fill_amount(),
+            amount_out_min: condition.execution_min_amount_out(current_price),
+            to: condition.owner.clone(),
+            deadline: env.ledger().timestamp() + config.swap_deadline_secs,
+            max_price_impact_bps: condition.max_price_impact_bps,
+        }
+    }
+
+    fn get_next_condition_id(env: &Env) -> Result<u64, Symbol> {
+        // initialize seeds the counter, so never mint ids on an uninitialized contract
         let current_id: u64 = env
             .storage()
             .instance()
             .get(&DataKey::NextConditionId)
-            .unwrap_or(1);
+            .ok_or_else(|| Symbol::new(env, "not_initialized"))?;
         
         env.storage().instance().set(&DataKey::NextConditionId, &(current_id + 1));
-        current_id
+        Ok(current_id)
     }
 
     fn add_user_condition(env: &Env, user: &Address, condition_id: u64) {
@@ -540,10 +2903,37 @@ impl SmartSwap {
         env.storage().instance().set(&DataKey::UserConditions(user.clone()), &user_conditions);
     }
 
+    fn remove_user_condition(env: &Env, user: &Address, condition_id: u64) {
+        let user_conditions = Self::get_user_conditions(env.clone(), user.clone());
+        let mut retained = Vec::new(env);
+        for id in user_conditions.iter() {
+            if id != condition_id {
+                retained.push_back(id);
+            }
+        }
+        env.storage().instance().set(&DataKey::UserConditions(user.clone()), &retained);
+    }
+
+    /// Fails when `new_conditions` more would take the contract-wide active count past
+    /// `max_total_active_conditions`, whichever users own them
+    fn check_global_condition_limit(
+        env: &Env,
+        config: &ContractConfig,
+        new_conditions: u32,
+    ) -> Result<(), Symbol> {
+        let stats = Self::get_global_stats(env.clone());
+        if stats.active_conditions_count.saturating_add(new_conditions as u64) > config.max_total_active_conditions {
+            return Err(Symbol::new(env, "global_condition_cap_reached"));
+        }
+        Ok(())
+    }
+
+    /// Fails when `new_conditions` more would take the user past `max_conditions` active ones
     fn check_user_condition_limit(
         env: &Env,
         user: &Address,
         max_conditions: u32,
+        new_conditions: u32,
     ) -> Result<(), Symbol> {
         let user_conditions: Vec<u64> = env
             .storage()
@@ -569,7 +2959,7 @@ impl SmartSwap {
             })
             .count();
 
-        if active_count >= max_conditions as usize {
+        if active_count + new_conditions as usize > max_conditions as usize {
             return Err(Symbol::new(env, "condition_limit_exceeded"));
         }
 
@@ -587,9 +2977,148 @@ impl SmartSwap {
             .get(&condition_id)
             .unwrap_or_else(|| Vec::new(env));
 
-        condition_executions.push_back(execution);
+        condition_executions.push_back(execution.clone());
         executions.set(condition_id, condition_executions);
         env.storage().instance().set(&DataKey::SwapExecutions, &executions);
+
+        // Append to the global feed, each entry under its own key so pages load O(limit)
+        let log_length: u32 = env.storage().instance().get(&DataKey::ExecutionLogLength).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::ExecutionLog(log_length), &execution);
+        env.storage().instance().set(&DataKey::ExecutionLogLength, &(log_length + 1));
+    }
+
+    fn refund_escrow(env: &Env, condition_id: u64, owner: &Address) {
+        let key = DataKey::ConditionEscrow(condition_id);
+        if let Some((token_address, amount)) = env.storage().instance().get::<DataKey, (Address, i128)>(&key) {
+            env.storage().instance().remove(&key);
+            token::Client::new(env, &token_address).transfer(&env.current_contract_address(), owner, &amount);
+        }
+    }
+
+    /// Credit `reward`, a share of `source_asset` swapped, to the keeper in reward token units.
+    /// Nothing accrues until a reward token is set.
+    fn accrue_keeper_reward(env: &Env, config: &ContractConfig, keeper: &Address, source_asset: &Symbol, reward: u64) {
+        if reward == 0 {
+            return;
+        }
+        let reward_asset = match env.storage().instance().get::<DataKey, (Symbol, Address)>(&DataKey::RewardToken) {
+            Some((reward_asset, _)) => reward_asset,
+            None => return,
+        };
+
+        let reward = if reward_asset == *source_asset {
+            reward
+        } else {
+            // The execution already stands, a reward that can't be valued is dropped rather than failing it
+            PriceOracleClient::estimate_swap_output(
+                env,
+                &config.oracle_config,
+                source_asset.clone(),
+                reward_asset.clone(),
+                reward,
+                Self::get_asset_decimals(env.clone(), source_asset.clone()),
+                Self::get_asset_decimals(env.clone(), reward_asset),
+            )
+            .unwrap_or(0)
+        };
+        if reward == 0 {
+            return;
+        }
+
+        let key = DataKey::KeeperRewards(keeper.clone());
+        let accrued: u64 = env.storage().instance().get(&key).unwrap_or(0);
+        env.storage().instance().set(&key, &accrued.saturating_add(reward));
+    }
+
+    fn accrue_protocol_fee(env: &Env, fee: u64) {
+        if fee == 0 {
+            return;
+        }
+        let collected: u64 = env.storage().instance().get(&DataKey::CollectedFees).unwrap_or(0);
+        env.storage().instance().set(&DataKey::CollectedFees, &collected.saturating_add(fee));
+    }
+
+    /// Replace `released` of an asset's pending volume with `reserved`
+    fn adjust_pending_volume(env: &Env, asset: &Symbol, released: u64, reserved: u64) {
+        let key = DataKey::PendingVolume(asset.clone());
+        let pending: u64 = env.storage().instance().get(&key).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&key, &pending.saturating_sub(released).saturating_add(reserved));
+    }
+
+    fn record_user_volume(env: &Env, user: &Address, amount: u64) {
+        let key = DataKey::UserVolume(user.clone());
+        let volume: u64 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &volume.saturating_add(amount));
+    }
+
+    /// Protocol fee for the owner's next execution, discounted by their volume tier
+    fn effective_fee_bps(env: &Env, config: &ContractConfig, owner: &Address) -> u32 {
+        match Self::get_user_fee_tier(env.clone(), owner.clone()) {
+            Some(tier) => tier.fee_bps.min(config.protocol_fee_bps),
+            None => config.protocol_fee_bps,
+        }
+    }
+
+    fn record_asset_volume(env: &Env, asset: &Symbol, amount: u64) {
+        let key = DataKey::AssetVolume(asset.clone());
+        let volume: u64 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &volume.saturating_add(amount));
+    }
+
+    fn enqueue_retry(env: &Env, condition_id: u64) {
+        let mut queue: Vec<u64> = env
+            .storage()
+            .instance()
+            .get(&DataKey::RetryQueue)
+            .unwrap_or_else(|| Vec::new(env));
+
+        if queue.contains(&condition_id) {
+            return;
+        }
+
+        // Drop the oldest entry to keep the queue bounded
+        if queue.len() >= MAX_RETRY_QUEUE_SIZE {
+            queue.pop_front();
+        }
+        queue.push_back(condition_id);
+        env.storage().instance().set(&DataKey::RetryQueue, &queue);
+    }
+
+    fn dequeue_retry(env: &Env, condition_id: u64) {
+        let mut queue: Vec<u64> = match env.storage().instance().get(&DataKey::RetryQueue) {
+            Some(queue) => queue,
+            None => return,
+        };
+
+        if let Some(index) = queue.first_index_of(condition_id) {
+            queue.remove(index);
+            env.storage().instance().set(&DataKey::RetryQueue, &queue);
+        }
+    }
+
+    fn record_price_sample(env: &Env, oracle_config: &OracleConfig, price_data: &PriceData) {
+        let history_key = DataKey::PriceHistory(price_data.asset_symbol.clone());
+        let mut history: Vec<PriceData> = env
+            .storage()
+            .persistent()
+            .get(&history_key)
+            .unwrap_or_else(|| Vec::new(env));
+
+        // Drop the oldest observations once the window is full, the cap may have been lowered since
+        while history.len() >= oracle_config.twap_max_samples && !history.is_empty() {
+            history.pop_front();
+        }
+        history.push_back(price_data.clone());
+        env.storage().persistent().set(&history_key, &history);
+    }
+
+    /// The only writer of `PausedStatus`, so the cached flags always match the config
+    fn store_pause_flags(env: &Env, mut config: ContractConfig, pause_flags: PauseFlags) {
+        env.storage().instance().set(&DataKey::PausedStatus, &pause_flags);
+        config.pause_flags = pause_flags;
+        env.storage().instance().set(&DataKey::Admin, &config);
     }
 
     fn update_global_stats<F>(env: &Env, update_fn: F)
@@ -601,6 +3130,29 @@ impl SmartSwap {
         env.storage().instance().set(&DataKey::GlobalStats, &stats);
     }
 
+    fn check_supported_assets(
+        env: &Env,
+        config: &ContractConfig,
+        source_asset: &Symbol,
+        destination_asset: &Symbol,
+    ) -> Result<(), Symbol> {
+        if !config.enforce_asset_whitelist {
+            return Ok(());
+        }
+
+        let supported_assets: Vec<Symbol> = env
+            .storage()
+            .instance()
+            .get(&DataKey::SupportedAssets)
+            .unwrap_or_else(|| Vec::new(env));
+
+        if !supported_assets.contains(source_asset) || !supported_assets.contains(destination_asset) {
+            return Err(Symbol::new(env, "unsupported_asset"));
+        }
+
+        Ok(())
+    }
+
     fn check_admin(env: &Env, caller: &Address) -> Result<(), Symbol> {
         let config: ContractConfig = env
             .storage()
@@ -615,14 +3167,20 @@ impl SmartSwap {
         Ok(())
     }
 
-    fn check_not_paused(env: &Env) -> Result<(), Symbol> {
+    fn check_not_paused(env: &Env, operation: PausableOperation) -> Result<(), Symbol> {
         let config: ContractConfig = env
             .storage()
             .instance()
             .get(&DataKey::Admin)
             .ok_or_else(|| Symbol::new(env, "not_initialized"))?;
 
-        if config.paused {
+        let paused = match operation {
+            PausableOperation::Create => config.pause_flags.create_paused,
+            PausableOperation::Execute => config.pause_flags.execute_paused,
+            PausableOperation::Cancel => config.pause_flags.cancel_paused,
+        };
+
+        if paused {
             return Err(Symbol::new(env, "contract_paused"));
         }
 
@@ -1,12 +1,18 @@
-use soroban_sdk::{contracttype, Address, Env, Symbol};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct OracleConfig {
     pub oracle_contract_address: Address,
+    pub oracle_addresses: Vec<Address>, // All oracles queried for median aggregation
     pub max_price_age: u64,        // Maximum age of price data in seconds
     pub fallback_enabled: bool,    // Whether to use fallback prices
     pub min_confidence: u32,       // Minimum confidence level required
+    pub twap_max_samples: u32,     // Maximum price samples retained per asset for TWAP
+    pub secondary_oracle_address: Option<Address>, // Optional second source for median-of-two pricing
+    pub max_oracle_divergence_bps: u32, // Maximum allowed primary/secondary divergence in basis points
+    pub max_fallback_price_age: u64, // Maximum age of fallback price data in seconds
+    pub min_fallback_confidence: u32, // Minimum confidence level required of fallback prices
 }
 
 #[contracttype]
@@ -17,6 +23,14 @@ pub struct PriceData {
     pub timestamp: u64,
     pub confidence: u32,
     pub source_count: u32,
+    pub is_fallback: bool, // Historical data served when the live oracle failed
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceSample {
+    pub price: u64,
+    pub timestamp: u64,
 }
 
 #[contracttype]
@@ -39,8 +53,18 @@ impl PriceOracleClient {
         oracle_config: &OracleConfig,
         asset_symbol: Symbol,
     ) -> PriceQueryResult {
+        // Aggregate across sources when more than one oracle is configured
+        if oracle_config.oracle_addresses.len() > 1 {
+            return Self::get_aggregated_price(env, oracle_config, asset_symbol);
+        }
+
+        // Cross-check against the secondary oracle when one is configured
+        if let Some(secondary_address) = &oracle_config.secondary_oracle_address {
+            return Self::get_dual_oracle_price(env, oracle_config, secondary_address, asset_symbol);
+        }
+
         // Try to get current price from oracle
-        match Self::query_oracle_price(env, oracle_config, asset_symbol.clone()) {
+        match Self::query_oracle_price(env, &oracle_config.oracle_contract_address, asset_symbol.clone()) {
             Ok(price_data) => {
                 // Validate price data quality
                 if Self::is_price_data_valid(env, &price_data, oracle_config) {
@@ -77,6 +101,195 @@ impl PriceOracleClient {
         }
     }
 
+    pub fn combine_dual_prices(
+        env: &Env,
+        oracle_config: &OracleConfig,
+        primary: &PriceData,
+        secondary: &PriceData,
+    ) -> PriceQueryResult {
+        if !Self::is_price_data_valid(env, primary, oracle_config)
+            || !Self::is_price_data_valid(env, secondary, oracle_config)
+        {
+            return PriceQueryResult {
+                success: false,
+                price_data: None,
+                error_message: Some(Symbol::new(env, "invalid_price_data")),
+            };
+        }
+
+        // Reject when the two sources disagree beyond the configured threshold
+        let (low, high) = if primary.price <= secondary.price {
+            (primary.price, secondary.price)
+        } else {
+            (secondary.price, primary.price)
+        };
+        let divergence_bps = ((high - low) as u128 * 10000) / low as u128;
+
+        if divergence_bps > oracle_config.max_oracle_divergence_bps as u128 {
+            return PriceQueryResult {
+                success: false,
+                price_data: None,
+                error_message: Some(Symbol::new(env, "oracle_divergence")),
+            };
+        }
+
+        PriceQueryResult {
+            success: true,
+            price_data: Some(PriceData {
+                asset_symbol: primary.asset_symbol.clone(),
+                price: ((low as u128 + high as u128) / 2) as u64, // Median of two
+                timestamp: primary.timestamp.min(secondary.timestamp),
+                confidence: primary.confidence.min(secondary.confidence),
+                source_count: primary.source_count + secondary.source_count,
+                is_fallback: false,
+            }),
+            error_message: None,
+        }
+    }
+
+    fn get_dual_oracle_price(
+        env: &Env,
+        oracle_config: &OracleConfig,
+        secondary_address: &Address,
+        asset_symbol: Symbol,
+    ) -> PriceQueryResult {
+        let primary = Self::query_oracle_price(env, &oracle_config.oracle_contract_address, asset_symbol.clone());
+        let secondary = Self::query_oracle_price(env, secondary_address, asset_symbol.clone());
+
+        match (primary, secondary) {
+            (Ok(primary), Ok(secondary)) => {
+                Self::combine_dual_prices(env, oracle_config, &primary, &secondary)
+            }
+            _ => {
+                if oracle_config.fallback_enabled {
+                    Self::get_fallback_price(env, oracle_config, asset_symbol)
+                } else {
+                    PriceQueryResult {
+                        success: false,
+                        price_data: None,
+                        error_message: Some(Symbol::new(env, "oracle_unavailable")),
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn get_aggregated_price(
+        env: &Env,
+        oracle_config: &OracleConfig,
+        asset_symbol: Symbol,
+    ) -> PriceQueryResult {
+        let mut responses = Vec::new(env);
+        for oracle_address in oracle_config.oracle_addresses.iter() {
+            if let Ok(price_data) = Self::query_oracle_price(env, &oracle_address, asset_symbol.clone()) {
+                responses.push_back(price_data);
+            }
+        }
+
+        Self::aggregate_price_responses(env, oracle_config, asset_symbol, &responses)
+    }
+
+    pub fn aggregate_price_responses(
+        env: &Env,
+        oracle_config: &OracleConfig,
+        asset_symbol: Symbol,
+        responses: &Vec<PriceData>,
+    ) -> PriceQueryResult {
+        // Discard stale and low-confidence responses
+        let mut prices = Vec::new(env);
+        let mut oldest_timestamp = u64::MAX;
+        let mut confidence_sum = 0u64;
+        for price_data in responses.iter() {
+            if Self::is_price_data_valid(env, &price_data, oracle_config) {
+                prices.push_back(price_data.price);
+                oldest_timestamp = oldest_timestamp.min(price_data.timestamp);
+                confidence_sum += price_data.confidence as u64;
+            }
+        }
+
+        // Quorum is the min_confidence share of configured oracles, at least one
+        let oracle_count = oracle_config.oracle_addresses.len().max(1);
+        let quorum = ((oracle_count * oracle_config.min_confidence + 99) / 100).max(1);
+
+        if prices.len() < quorum {
+            if oracle_config.fallback_enabled {
+                return Self::get_fallback_price(env, oracle_config, asset_symbol);
+            }
+
+            return PriceQueryResult {
+                success: false,
+                price_data: None,
+                error_message: Some(Symbol::new(env, "insufficient_oracle_quorum")),
+            };
+        }
+
+        let source_count = prices.len();
+        PriceQueryResult {
+            success: true,
+            price_data: Some(PriceData {
+                asset_symbol,
+                price: Self::median_price(prices),
+                timestamp: oldest_timestamp,
+                confidence: (confidence_sum / source_count as u64) as u32,
+                source_count,
+                is_fallback: false,
+            }),
+            error_message: None,
+        }
+    }
+
+    pub fn median_price(mut prices: Vec<u64>) -> u64 {
+        let len = prices.len();
+        if len == 0 {
+            return 0;
+        }
+
+        // Insertion sort, oracle sets are small
+        for i in 1..len {
+            let mut j = i;
+            while j > 0 && prices.get(j - 1).unwrap() > prices.get(j).unwrap() {
+                let previous = prices.get(j - 1).unwrap();
+                prices.set(j - 1, prices.get(j).unwrap());
+                prices.set(j, previous);
+                j -= 1;
+            }
+        }
+
+        let mid = len / 2;
+        if len % 2 == 0 {
+            let lower = prices.get(mid - 1).unwrap() as u128;
+            let upper = prices.get(mid).unwrap() as u128;
+            ((lower + upper) / 2) as u64
+        } else {
+            prices.get(mid).unwrap()
+        }
+    }
+
+    /// Price each asset independently, keeping input order. A failing asset yields a failed
+    /// result instead of aborting the batch.
+    pub fn get_multiple_prices_lenient(
+        env: &Env,
+        oracle_config: &OracleConfig,
+        asset_symbols: &[Symbol],
+    ) -> soroban_sdk::Vec<PriceQueryResult> {
+        let mut results = soroban_sdk::Vec::new(env);
+
+        for asset_symbol in asset_symbols {
+            let result = Self::get_price(env, oracle_config, asset_symbol.clone());
+            if result.success && result.price_data.is_none() {
+                results.push_back(PriceQueryResult {
+                    success: false,
+                    price_data: None,
+                    error_message: Some(Symbol::new(env, "missing_price_data")),
+                });
+            } else {
+                results.push_back(result);
+            }
+        }
+
+        results
+    }
+
     pub fn get_multiple_prices(
         env: &Env,
         oracle_config: &OracleConfig,
@@ -100,12 +313,29 @@ impl PriceOracleClient {
         Ok(prices)
     }
 
+    /// Destination base units one whole unit of the source buys at oracle prices. With the default
+    /// 7 decimals on both sides this is the price ratio scaled by `PRICE_SCALING_FACTOR`.
     pub fn calculate_exchange_rate(
         env: &Env,
         oracle_config: &OracleConfig,
         from_asset: Symbol,
         to_asset: Symbol,
+        from_decimals: u32,
+        to_decimals: u32,
     ) -> Result<u64, Symbol> {
+        let (from_price, to_price) = Self::get_pair_prices(env, oracle_config, from_asset, to_asset)?;
+
+        let exchange_rate = Self::convert_amount(10u128.pow(from_decimals), from_price, to_price, from_decimals, to_decimals);
+        Ok(exchange_rate.min(u64::MAX as u128) as u64)
+    }
+
+    /// Whole-unit prices of both assets, rejecting a zero destination price
+    fn get_pair_prices(
+        env: &Env,
+        oracle_config: &OracleConfig,
+        from_asset: Symbol,
+        to_asset: Symbol,
+    ) -> Result<(u64, u64), Symbol> {
         let from_price_result = Self::get_price(env, oracle_config, from_asset.clone());
         let to_price_result = Self::get_price(env, oracle_config, to_asset.clone());
 
@@ -120,9 +350,34 @@ impl PriceOracleClient {
             return Err(Symbol::new(env, "zero_destination_price"));
         }
 
-        // Calculate exchange rate: (from_price / to_price) * scaling_factor
-        let exchange_rate = (from_price.price * 1_0000000) / to_price.price; // Scale by 7 decimals
-        Ok(exchange_rate)
+        Ok((from_price.price, to_price.price))
+    }
+
+    pub fn calculate_twap(env: &Env, samples: &Vec<PriceData>) -> Result<u64, Symbol> {
+        if samples.is_empty() {
+            return Err(Symbol::new(env, "no_price_samples"));
+        }
+
+        let first = samples.get(0).unwrap();
+        let last = samples.get(samples.len() - 1).unwrap();
+        let total_duration = last.timestamp.saturating_sub(first.timestamp);
+
+        // All samples share a timestamp, fall back to a simple average
+        if total_duration == 0 {
+            let sum: u128 = samples.iter().map(|sample| sample.price as u128).sum();
+            return Ok((sum / samples.len() as u128) as u64);
+        }
+
+        // Weight each price by how long it was in effect until the next sample
+        let mut weighted_sum: u128 = 0;
+        for i in 0..samples.len() - 1 {
+            let current = samples.get(i).unwrap();
+            let next = samples.get(i + 1).unwrap();
+            let duration = next.timestamp.saturating_sub(current.timestamp);
+            weighted_sum += current.price as u128 * duration as u128;
+        }
+
+        Ok((weighted_sum / total_duration as u128) as u64)
     }
 
     pub fn validate_price_for_swap(
@@ -130,14 +385,21 @@ impl PriceOracleClient {
         price_data: &PriceData,
         oracle_config: &OracleConfig,
     ) -> Result<(), Symbol> {
+        // Fallback data is older and less confident by nature, so it has its own thresholds
+        let (max_price_age, min_confidence) = if price_data.is_fallback {
+            (oracle_config.max_fallback_price_age, oracle_config.min_fallback_confidence)
+        } else {
+            (oracle_config.max_price_age, oracle_config.min_confidence)
+        };
+
         // Check price age
         let current_time = env.ledger().timestamp();
-        if current_time.saturating_sub(price_data.timestamp) > oracle_config.max_price_age {
+        if current_time.saturating_sub(price_data.timestamp) > max_price_age {
             return Err(Symbol::new(env, "price_too_old"));
         }
 
         // Check confidence level
-        if price_data.confidence < oracle_config.min_confidence {
+        if price_data.confidence < min_confidence {
             return Err(Symbol::new(env, "insufficient_confidence"));
         }
 
@@ -152,14 +414,14 @@ impl PriceOracleClient {
     // Internal helper methods
     fn query_oracle_price(
         env: &Env,
-        oracle_config: &OracleConfig,
+        oracle_address: &Address,
         asset_symbol: Symbol,
     ) -> Result<PriceData, Symbol> {
         // This would call the actual price oracle contract
         // For now, we'll simulate the call
         
         // In a real implementation, this would be:
-        // let client = PriceOracleContractClient::new(env, &oracle_config.oracle_contract_address);
+        // let client = PriceOracleContractClient::new(env, oracle_address);
         // let aggregated_price = client.get_price(&asset_symbol)?;
         
         // Simulate oracle response
@@ -180,6 +442,7 @@ impl PriceOracleClient {
             timestamp: current_time,
             confidence: 85, // 85% confidence
             source_count: 5, // 5 oracle sources
+            is_fallback: false,
         })
     }
 
@@ -194,10 +457,17 @@ impl PriceOracleClient {
 
         // For now, simulate fallback logic
         match Self::query_historical_price(env, asset_symbol.clone()) {
-            Ok(price_data) => PriceQueryResult {
-                success: true,
-                price_data: Some(price_data),
-                error_message: None,
+            Ok(price_data) => match Self::validate_price_for_swap(env, &price_data, oracle_config) {
+                Ok(()) => PriceQueryResult {
+                    success: true,
+                    price_data: Some(price_data),
+                    error_message: None,
+                },
+                Err(_) => PriceQueryResult {
+                    success: false,
+                    price_data: None,
+                    error_message: Some(Symbol::new(env, "invalid_fallback_price")),
+                },
             },
             Err(error) => PriceQueryResult {
                 success: false,
@@ -226,6 +496,7 @@ impl PriceOracleClient {
             timestamp: current_time - 300, // 5 minutes ago
             confidence: 70, // Lower confidence for historical data
             source_count: 3, // Fewer sources for historical data
+            is_fallback: true,
         })
     }
 
@@ -259,22 +530,37 @@ impl PriceOracleClient {
         true
     }
 
+    /// Oracle-implied output in base units of `to_asset`. Prices and the exchange rate are per
+    /// whole unit, so the amount is rescaled between the two assets' decimals.
     pub fn estimate_swap_output(
         env: &Env,
         oracle_config: &OracleConfig,
         from_asset: Symbol,
         to_asset: Symbol,
         amount_in: u64,
+        from_decimals: u32,
+        to_decimals: u32,
     ) -> Result<u64, Symbol> {
-        let exchange_rate = Self::calculate_exchange_rate(env, oracle_config, from_asset, to_asset)?;
-        
-        if exchange_rate == 0 {
+        let (from_price, to_price) = Self::get_pair_prices(env, oracle_config, from_asset, to_asset)?;
+
+        // Work from the prices rather than the rounded exchange rate
+        let estimated_output = Self::convert_amount(amount_in as u128, from_price, to_price, from_decimals, to_decimals);
+
+        if estimated_output == 0 {
             return Err(Symbol::new(env, "zero_exchange_rate"));
         }
+        Ok(estimated_output.min(u64::MAX as u128) as u64)
+    }
 
-        // Calculate expected output: (amount_in * exchange_rate) / scaling_factor
-        let estimated_output = (amount_in * exchange_rate) / 1_0000000;
-        Ok(estimated_output)
+    /// Value `amount` base units of the source in destination base units, in u128 so the decimal
+    /// rescaling can't overflow
+    fn convert_amount(amount: u128, from_price: u64, to_price: u64, from_decimals: u32, to_decimals: u32) -> u128 {
+        let value = amount.saturating_mul(from_price as u128);
+        if to_decimals >= from_decimals {
+            value.saturating_mul(10u128.pow(to_decimals - from_decimals)) / to_price as u128
+        } else {
+            value / (to_price as u128 * 10u128.pow(from_decimals - to_decimals))
+        }
     }
 
     pub fn get_price_impact(
@@ -342,14 +628,29 @@ pub struct OracleConfigManager;
 impl OracleConfigManager {
     pub fn create_default_config(env: &Env, oracle_address: Address) -> OracleConfig {
         OracleConfig {
-            oracle_contract_address: oracle_address,
+            oracle_contract_address: oracle_address.clone(),
+            oracle_addresses: Vec::from_array(env, [oracle_address]),
             max_price_age: 300,        // 5 minutes
             fallback_enabled: true,
             min_confidence: 70,        // 70% minimum confidence
+            twap_max_samples: DEFAULT_TWAP_MAX_SAMPLES,
+            secondary_oracle_address: None,
+            max_oracle_divergence_bps: DEFAULT_MAX_ORACLE_DIVERGENCE_BPS,
+            max_fallback_price_age: DEFAULT_MAX_FALLBACK_PRICE_AGE,
+            min_fallback_confidence: DEFAULT_MIN_FALLBACK_CONFIDENCE,
         }
     }
 
     pub fn validate_config(env: &Env, config: &OracleConfig) -> Result<(), Symbol> {
+        if config.oracle_addresses.is_empty() {
+            return Err(Symbol::new(env, "no_oracle_addresses"));
+        }
+
+        // Median aggregation ignores the secondary oracle, so setting both would leave one unused
+        if config.secondary_oracle_address.is_some() && config.oracle_addresses.len() > 1 {
+            return Err(Symbol::new(env, "conflicting_oracle_sources"));
+        }
+
         // Validate max price age (should be reasonable)
         if config.max_price_age == 0 || config.max_price_age > 3600 {
             return Err(Symbol::new(env, "invalid_max_price_age"));
@@ -360,6 +661,25 @@ impl OracleConfigManager {
             return Err(Symbol::new(env, "invalid_min_confidence"));
         }
 
+        // Validate fallback thresholds
+        if config.max_fallback_price_age == 0 || config.max_fallback_price_age > MAX_PRICE_AGE_LIMIT {
+            return Err(Symbol::new(env, "invalid_max_fallback_price_age"));
+        }
+
+        if config.min_fallback_confidence > 100 {
+            return Err(Symbol::new(env, "invalid_min_fallback_confidence"));
+        }
+
+        // Validate oracle divergence threshold
+        if config.max_oracle_divergence_bps == 0 || config.max_oracle_divergence_bps > 10000 {
+            return Err(Symbol::new(env, "invalid_oracle_divergence"));
+        }
+
+        // Validate TWAP sample buffer size
+        if config.twap_max_samples < MIN_TWAP_SAMPLES || config.twap_max_samples > MAX_TWAP_SAMPLES {
+            return Err(Symbol::new(env, "invalid_twap_max_samples"));
+        }
+
         Ok(())
     }
 }
@@ -367,6 +687,14 @@ impl OracleConfigManager {
 // Constants for oracle integration
 pub const DEFAULT_MAX_PRICE_AGE: u64 = 300;      // 5 minutes
 pub const DEFAULT_MIN_CONFIDENCE: u32 = 70;       // 70%
+pub const DEFAULT_MAX_FALLBACK_PRICE_AGE: u64 = 900; // 15 minutes
+pub const DEFAULT_MIN_FALLBACK_CONFIDENCE: u32 = 60; // 60%
 pub const MAX_PRICE_AGE_LIMIT: u64 = 3600;        // 1 hour
 pub const MIN_CONFIDENCE_LIMIT: u32 = 50;         // 50%
-pub const PRICE_SCALING_FACTOR: u64 = 1_0000000;  // 7 decimal places
\ No newline at end of file
+pub const PRICE_SCALING_FACTOR: u64 = 1_0000000;  // 7 decimal places
+pub const DEFAULT_ASSET_DECIMALS: u32 = 7;        // Stellar asset convention
+pub const MAX_ASSET_DECIMALS: u32 = 18;
+pub const DEFAULT_TWAP_MAX_SAMPLES: u32 = 24;     // 24 samples retained per asset
+pub const MIN_TWAP_SAMPLES: u32 = 2;              // Need at least two points for a TWAP
+pub const MAX_TWAP_SAMPLES: u32 = 200;            // Upper bound on per-asset storage
+pub const DEFAULT_MAX_ORACLE_DIVERGENCE_BPS: u32 = 200; // 2% primary/secondary divergence
\ No newline at end of file
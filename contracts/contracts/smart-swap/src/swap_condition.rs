@@ -1,25 +1,47 @@
-use soroban_sdk::{contracttype, Address, Env, Symbol};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+use crate::{PriceSample, MAX_PRICE_IMPACT_BPS};
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum SwapConditionType {
     PercentageIncrease(u32), // Percentage increase (e.g., 10 = 10%)
     PercentageDecrease(u32), // Percentage decrease (e.g., 15 = 15%)
-    TargetPrice(u64),        // Specific target price in stroops
+    TargetPrice(u64, u32),   // Specific target price in stroops, tolerance in basis points
     PriceAbove(u64),         // Execute when price goes above this value
     PriceBelow(u64),         // Execute when price goes below this value
+    LimitOrder(u64, OrderDirection), // Execute when price crosses the limit in the given direction
+    TwapAbove(u64, u64),     // Execute when the TWAP over a window (seconds) goes above this value
+    RateAbove(u64),          // Execute when the source/destination exchange rate goes above this value
+    RateBelow(u64),          // Execute when the source/destination exchange rate goes below this value
+    TimeReached(u64),        // Execute once the ledger timestamp reaches this value, regardless of price
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OrderDirection {
+    Buy,  // Fires when price <= limit price
+    Sell, // Fires when price >= limit price
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum SwapStatus {
     Active,
+    Executing, // Transient, set while the DEX swap is in flight
     Executed,
     Cancelled,
     Failed,
     Expired,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExecutionMode {
+    DexQuote,     // amount_out comes from the DEX quote
+    OraclePriced, // amount_out comes from the oracle exchange rate
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SwapCondition {
@@ -38,6 +60,31 @@ pub struct SwapCondition {
     pub last_check: u64,
     pub execution_count: u32, // For recurring swaps
     pub max_executions: u32,  // 0 means unlimited
+    pub price_observations: Vec<PriceSample>, // Recent prices for TWAP conditions
+    pub auto_renew: bool,     // Extend expiry automatically when it nears
+    pub renewal_period: u64,  // Seconds added to expires_at on each renewal
+    pub max_renewals: u32,
+    pub renewal_count: u32,
+    pub max_price_impact_bps: u32, // Reject execution when the DEX quote impact exceeds this
+    pub activates_at: u64,    // Condition can't execute before this time
+    pub chunk_size: u64,      // Max amount swapped per execution, 0 fills fully
+    pub amount_remaining: u64, // Unfilled part of the current order
+    pub execution_interval: u64, // Minimum seconds between executions
+    pub last_executed_at: Option<u64>,
+    pub memo: Option<Symbol>,  // Owner-editable note
+    pub min_confidence_override: Option<u32>, // Stricter oracle confidence floor for this condition
+    pub total_budget: u64,    // Cap on cumulative amount_in across executions, 0 means unlimited
+    pub total_spent: u64,     // Cumulative amount_in swapped so far
+    pub average_execution_price: u64, // Volume-weighted across all fills, 0 before the first
+    pub total_filled: u64,    // Fill volume behind average_execution_price
+    pub best_execution_price: Option<u64>,  // Highest source price received for a fill
+    pub worst_execution_price: Option<u64>, // Lowest source price received for a fill
+    pub min_amount_out_override: Option<u64>, // Owner-set absolute minimum output for the full order
+    pub retry_count: u32,     // Consecutive retryable execution failures
+    pub max_retries: u32,     // Retryable failures tolerated before the condition fails
+    pub execution_mode: ExecutionMode,
+    pub label: Option<Symbol>, // Client-side grouping, never read by execution
+    pub enforce_reference_price: bool, // Sell-higher conditions refuse fills worth less than the reference
 }
 
 #[contracttype]
@@ -51,6 +98,10 @@ pub struct SwapExecution {
     pub actual_slippage: u32, // In basis points
     pub gas_used: u64,
     pub tx_hash: Symbol, // Transaction hash as Symbol
+    pub crossing_price: Option<u64>, // Price that crossed the limit (limit orders only)
+    pub keeper_reward: u64,          // Reward accrued by the executing keeper
+    pub protocol_fee: u64,           // Protocol fee recorded for this execution
+    pub price_impact: u32,           // DEX price impact in basis points
 }
 
 #[contracttype]
@@ -63,6 +114,31 @@ pub struct CreateSwapRequest {
     pub max_slippage: u32,
     pub expires_at: u64,
     pub max_executions: u32,
+    pub auto_renew: bool,
+    pub renewal_period: u64,
+    pub max_renewals: u32,
+    pub max_price_impact_bps: u32,
+    pub activation_delay_secs: u64,
+    pub chunk_size: u64,
+    pub execution_interval: u64,
+    pub min_confidence_override: Option<u32>,
+    pub total_budget: u64,
+    pub min_amount_out_override: Option<u64>,
+    pub max_retries: u32,
+    pub execution_mode: ExecutionMode,
+    pub label: Option<Symbol>,
+    pub enforce_reference_price: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConditionCostEstimate {
+    pub creation_gas: u64,
+    pub per_check_gas: u64,
+    pub execution_gas: u64,
+    pub estimated_checks: u64,   // Checks over the lifetime at the assumed keeper interval
+    pub storage_rent: u64,       // Rent over the condition lifetime in stroops
+    pub total_cost: u64,         // Gas plus rent, all in stroops
 }
 
 #[contracttype]
@@ -77,10 +153,23 @@ pub const MAX_SLIPPAGE_BASIS_POINTS: u32 = 5000; // 50% maximum slippage
 pub const MIN_SLIPPAGE_BASIS_POINTS: u32 = 1;    // 0.01% minimum slippage
 pub const MAX_SWAP_AMOUNT: u64 = 1_000_000_0000000; // 1M XLM equivalent
 pub const MIN_SWAP_AMOUNT: u64 = 1_0000000;         // 1 XLM minimum
+pub const MAX_MIN_OUT_MULTIPLIER: u64 = 1_000_000;   // Override may ask for at most this many out per unit in
 pub const MAX_CONDITION_LIFETIME: u64 = 86400 * 365; // 1 year maximum
 pub const MIN_CONDITION_LIFETIME: u64 = 60;          // 1 minute minimum
 pub const MAX_PERCENTAGE_CHANGE: u32 = 10000;        // 100% maximum change
 pub const MIN_PERCENTAGE_CHANGE: u32 = 1;            // 0.01% minimum change
+pub const DEFAULT_TARGET_TOLERANCE_BPS: u32 = 10;    // 0.1% band around target price
+pub const MAX_TWAP_OBSERVATIONS: u32 = 16;           // Observations kept per TWAP condition
+pub const AUTO_RENEW_WINDOW: u64 = 3600;             // Renew within 1 hour of expiry
+pub const MAX_RENEWALS: u32 = 365;                   // Cap on automatic renewals
+pub const MAX_EXECUTION_RETRIES: u32 = 10;           // Cap on retryable execution failures
+
+// Heuristics for condition cost estimates
+pub const CREATION_GAS_ESTIMATE: u64 = 150_000;      // Validation, oracle read and storage writes
+pub const CHECK_GAS_ESTIMATE: u64 = 50_000;          // Oracle read and trigger evaluation
+pub const ASSUMED_CHECK_INTERVAL: u64 = 300;         // Keepers check every 5 minutes
+pub const CONDITION_STORAGE_BYTES: u64 = 512;        // Approximate serialized condition size
+pub const RENT_PER_BYTE_PER_DAY: u64 = 1;            // Stroops per byte per day
 
 impl SwapCondition {
     pub fn new(
@@ -89,21 +178,21 @@ impl SwapCondition {
         owner: Address,
         request: CreateSwapRequest,
         reference_price: u64,
-    ) -> Self {
+    ) -> Result<Self, Symbol> {
         let current_time = env.ledger().timestamp();
-        
-        Self {
+        let min_amount_out = match request.min_amount_out_override {
+            Some(min_amount_out) => min_amount_out,
+            None => Self::calculate_min_amount_out(env, request.amount_to_swap, reference_price, request.max_slippage)?,
+        };
+
+        Ok(Self {
             id,
             owner,
             source_asset: request.source_asset,
             destination_asset: request.destination_asset,
             condition_type: request.condition_type,
             amount_to_swap: request.amount_to_swap,
-            min_amount_out: Self::calculate_min_amount_out(
-                request.amount_to_swap,
-                reference_price,
-                request.max_slippage,
-            ),
+            min_amount_out,
             max_slippage: request.max_slippage,
             reference_price,
             created_at: current_time,
@@ -112,9 +201,184 @@ impl SwapCondition {
             last_check: current_time,
             execution_count: 0,
             max_executions: request.max_executions,
+            price_observations: Vec::new(env),
+            auto_renew: request.auto_renew,
+            renewal_period: request.renewal_period,
+            max_renewals: request.max_renewals,
+            renewal_count: 0,
+            max_price_impact_bps: request.max_price_impact_bps,
+            activates_at: current_time.saturating_add(request.activation_delay_secs),
+            chunk_size: request.chunk_size,
+            amount_remaining: request.amount_to_swap,
+            execution_interval: request.execution_interval,
+            last_executed_at: None,
+            memo: None,
+            min_confidence_override: request.min_confidence_override,
+            total_budget: request.total_budget,
+            total_spent: 0,
+            average_execution_price: 0,
+            total_filled: 0,
+            best_execution_price: None,
+            worst_execution_price: None,
+            min_amount_out_override: request.min_amount_out_override,
+            retry_count: 0,
+            max_retries: request.max_retries,
+            execution_mode: request.execution_mode,
+            label: request.label,
+            enforce_reference_price: request.enforce_reference_price,
+        })
+    }
+
+    /// Rebuild the creation request for this condition, used to re-validate modifications
+    pub fn to_request(&self, current_time: u64) -> CreateSwapRequest {
+        CreateSwapRequest {
+            source_asset: self.source_asset.clone(),
+            destination_asset: self.destination_asset.clone(),
+            condition_type: self.condition_type.clone(),
+            amount_to_swap: self.amount_to_swap,
+            max_slippage: self.max_slippage,
+            expires_at: self.expires_at,
+            max_executions: self.max_executions,
+            auto_renew: self.auto_renew,
+            renewal_period: self.renewal_period,
+            max_renewals: self.max_renewals,
+            max_price_impact_bps: self.max_price_impact_bps,
+            activation_delay_secs: self.activates_at.saturating_sub(current_time),
+            chunk_size: self.chunk_size,
+            execution_interval: self.execution_interval,
+            min_confidence_override: self.min_confidence_override,
+            total_budget: self.total_budget,
+            min_amount_out_override: self.min_amount_out_override,
+            max_retries: self.max_retries,
+            execution_mode: self.execution_mode.clone(),
+            label: self.label.clone(),
+            enforce_reference_price: self.enforce_reference_price,
+        }
+    }
+
+    pub fn apply_update(
+        &mut self,
+        env: &Env,
+        max_slippage: u32,
+        expires_at: u64,
+        condition_type: SwapConditionType,
+    ) -> Result<(), Symbol> {
+        if self.min_amount_out_override.is_none() {
+            self.min_amount_out = Self::calculate_min_amount_out(env, self.amount_to_swap, self.reference_price, max_slippage)?;
+        }
+        self.max_slippage = max_slippage;
+        self.expires_at = expires_at;
+        self.condition_type = condition_type;
+        Ok(())
+    }
+
+    /// Distance of `current_price` from the reference price in basis points of the reference
+    pub fn price_deviation_bps(&self, current_price: u64) -> u32 {
+        if self.reference_price == 0 {
+            return 0;
+        }
+        let difference = current_price.abs_diff(self.reference_price) as u128;
+        let deviation = difference * 10000 / self.reference_price as u128;
+        if deviation > u32::MAX as u128 {
+            u32::MAX
+        } else {
+            deviation as u32
+        }
+    }
+
+    /// True when `other` swaps the same pair in the opposite direction
+    pub fn conflicts_with(&self, other: &SwapCondition) -> bool {
+        self.source_asset == other.destination_asset && self.destination_asset == other.source_asset
+    }
+
+    /// Oracle confidence required to execute, never looser than the global minimum
+    pub fn effective_min_confidence(&self, global_min_confidence: u32) -> u32 {
+        match self.min_confidence_override {
+            Some(confidence) => confidence.max(global_min_confidence),
+            None => global_min_confidence,
+        }
+    }
+
+    /// Executions left before `max_executions` is reached, None when unlimited
+    pub fn remaining_executions(&self) -> Option<u32> {
+        if self.max_executions == 0 {
+            None
+        } else {
+            Some(self.max_executions.saturating_sub(self.execution_count))
+        }
+    }
+
+    pub fn is_activated(&self, current_time: u64) -> bool {
+        current_time >= self.activates_at
+    }
+
+    /// Earliest time the next execution is allowed, honouring activation and the execution interval
+    pub fn next_execution_time(&self) -> u64 {
+        match self.last_executed_at {
+            Some(last_executed_at) => self.activates_at.max(last_executed_at.saturating_add(self.execution_interval)),
+            None => self.activates_at,
+        }
+    }
+
+    /// Amount to swap on the next execution
+    pub fn next_fill_amount(&self) -> u64 {
+        let fill = if self.chunk_size == 0 || self.chunk_size > self.amount_remaining {
+            self.amount_remaining
+        } else {
+            self.chunk_size
+        };
+
+        // The last execution under a budget only spends what's left of it
+        if self.total_budget > 0 {
+            fill.min(self.total_budget.saturating_sub(self.total_spent))
+        } else {
+            fill
+        }
+    }
+
+    /// Minimum output for the next fill, pro rata to the full order minimum
+    pub fn next_fill_min_amount_out(&self) -> u64 {
+        if self.amount_to_swap == 0 {
+            return 0;
+        }
+        let min_out = (self.min_amount_out as u128 * self.next_fill_amount() as u128) / self.amount_to_swap as u128;
+        if min_out > u64::MAX as u128 {
+            u64::MAX
+        } else {
+            min_out as u64
+        }
+    }
+
+    /// Minimum output for the next fill at the current price. The stored `min_amount_out` is
+    /// priced at the reference and goes stale once the market moves to the trigger, unless
+    /// the owner set it explicitly.
+    pub fn execution_min_amount_out(&self, current_price: u64) -> u64 {
+        if self.min_amount_out_override.is_some() || self.amount_to_swap == 0 || self.reference_price == 0 {
+            return self.next_fill_min_amount_out();
+        }
+        let expected = self.calculate_expected_output(current_price) as u128;
+        let min_out = expected * self.next_fill_amount() as u128 / self.amount_to_swap as u128;
+        if min_out > u64::MAX as u128 {
+            u64::MAX
+        } else {
+            min_out as u64
         }
     }
 
+    /// Smallest slippage in basis points at which `amount_out` for the next fill would meet
+    /// the execution minimum at `current_price`
+    pub fn required_slippage_bps(&self, current_price: u64, amount_out: u64) -> u32 {
+        if self.reference_price == 0 {
+            return 0;
+        }
+        let expected = self.next_fill_amount() as u128 * current_price as u128 / self.reference_price as u128;
+        if expected == 0 || amount_out as u128 >= expected {
+            return 0;
+        }
+        let retained_bps = amount_out as u128 * 10000 / expected;
+        10000 - retained_bps as u32
+    }
+
     pub fn is_valid(&self, env: &Env) -> Result<(), SwapValidationError> {
         let current_time = env.ledger().timestamp();
 
@@ -156,6 +420,17 @@ impl SwapCondition {
                 error_code: 1006,
                 message: Symbol::new(env, "condition_expired"),
             }),
+            // A spending budget can end a condition before its execution limit
+            SwapStatus::Executed if self.total_budget > 0 && self.total_spent >= self.total_budget => {
+                Err(SwapValidationError {
+                    error_code: 1007,
+                    message: Symbol::new(env, "budget_exhausted"),
+                })
+            }
+            SwapStatus::Executed => Err(SwapValidationError {
+                error_code: 1008,
+                message: Symbol::new(env, "condition_executed"),
+            }),
             _ => Ok(()),
         }
     }
@@ -163,43 +438,257 @@ impl SwapCondition {
     pub fn should_execute(&self, current_price: u64) -> bool {
         match &self.condition_type {
             SwapConditionType::PercentageIncrease(percentage) => {
-                let increase_required = (self.reference_price * (*percentage as u64)) / 100;
-                current_price >= self.reference_price + increase_required
+                current_price >= Self::increase_threshold(self.reference_price, *percentage)
             }
             SwapConditionType::PercentageDecrease(percentage) => {
-                let decrease_required = (self.reference_price * (*percentage as u64)) / 100;
-                current_price <= self.reference_price.saturating_sub(decrease_required)
+                current_price <= Self::decrease_threshold(self.reference_price, *percentage)
             }
-            SwapConditionType::TargetPrice(target) => {
-                // Allow small tolerance around target price (0.1%)
-                let tolerance = target / 1000;
+            SwapConditionType::TargetPrice(target, tolerance_bps) => {
+                // Allow configured tolerance around target price
+                let tolerance = ((*target as u128 * *tolerance_bps as u128) / 10000) as u64;
                 current_price >= target.saturating_sub(tolerance)
-                    && current_price <= target + tolerance
+                    && current_price <= target.saturating_add(tolerance)
             }
             SwapConditionType::PriceAbove(threshold) => current_price > *threshold,
             SwapConditionType::PriceBelow(threshold) => current_price < *threshold,
+            SwapConditionType::LimitOrder(limit_price, direction) => match direction {
+                OrderDirection::Buy => current_price <= *limit_price,
+                OrderDirection::Sell => current_price >= *limit_price,
+            },
+            SwapConditionType::TwapAbove(threshold, window) => match self.calculate_twap(*window) {
+                Some(twap) => twap > *threshold,
+                None => false,
+            },
+            SwapConditionType::RateAbove(rate) => current_price > *rate,
+            SwapConditionType::RateBelow(rate) => current_price < *rate,
+            SwapConditionType::TimeReached(target) => current_price >= *target,
+        }
+    }
+
+    /// Percentage conditions trigger relative to `reference_price`
+    pub fn is_percentage_based(&self) -> bool {
+        matches!(
+            self.condition_type,
+            SwapConditionType::PercentageIncrease(_) | SwapConditionType::PercentageDecrease(_)
+        )
+    }
+
+    /// Rate conditions are evaluated against the source/destination exchange rate rather than
+    /// the source price, callers pass that rate to `should_execute`
+    pub fn uses_exchange_rate(&self) -> bool {
+        matches!(
+            self.condition_type,
+            SwapConditionType::RateAbove(_) | SwapConditionType::RateBelow(_)
+        )
+    }
+
+    /// Time conditions are evaluated against the ledger timestamp rather than a price,
+    /// callers pass that timestamp to `should_execute`
+    pub fn is_time_based(&self) -> bool {
+        matches!(self.condition_type, SwapConditionType::TimeReached(_))
+    }
+
+    pub fn try_auto_renew(&mut self, current_time: u64) -> bool {
+        if !self.auto_renew || self.renewal_count >= self.max_renewals {
+            return false;
+        }
+
+        if current_time > self.expires_at || self.expires_at - current_time > AUTO_RENEW_WINDOW {
+            return false;
+        }
+
+        self.expires_at = self.expires_at.saturating_add(self.renewal_period);
+        self.renewal_count += 1;
+        true
+    }
+
+    pub fn record_observation(&mut self, timestamp: u64, price: u64) {
+        let window = match &self.condition_type {
+            SwapConditionType::TwapAbove(_, window) => *window,
+            _ => return,
+        };
+
+        self.price_observations.push_back(PriceSample { price, timestamp });
+
+        // Drop samples older than the window, keeping one anchor at or before the window start
+        let window_start = timestamp.saturating_sub(window);
+        while self.price_observations.len() >= 2
+            && self.price_observations.get(1).unwrap().timestamp <= window_start
+        {
+            self.price_observations.pop_front();
+        }
+
+        while self.price_observations.len() > MAX_TWAP_OBSERVATIONS {
+            self.price_observations.pop_front();
+        }
+    }
+
+    // TWAP over [latest - window, latest], None until observations cover the full window
+    pub fn calculate_twap(&self, window: u64) -> Option<u64> {
+        if self.price_observations.is_empty() || window == 0 {
+            return None;
+        }
+
+        let latest = self.price_observations.get(self.price_observations.len() - 1).unwrap();
+        let window_start = latest.timestamp.saturating_sub(window);
+        if self.price_observations.get(0).unwrap().timestamp > window_start {
+            return None;
+        }
+
+        // Each price holds from its sample (clamped to window start) until the next sample
+        let mut weighted_sum: u128 = 0;
+        for i in 0..self.price_observations.len() - 1 {
+            let current = self.price_observations.get(i).unwrap();
+            let next = self.price_observations.get(i + 1).unwrap();
+            let duration = next.timestamp.saturating_sub(current.timestamp.max(window_start));
+            weighted_sum += current.price as u128 * duration as u128;
+        }
+
+        Some((weighted_sum / window as u128) as u64)
+    }
+
+    pub fn trigger_price(&self) -> Option<u64> {
+        match &self.condition_type {
+            SwapConditionType::PercentageIncrease(percentage) => {
+                Some(Self::increase_threshold(self.reference_price, *percentage))
+            }
+            SwapConditionType::PercentageDecrease(percentage) => {
+                Some(Self::decrease_threshold(self.reference_price, *percentage))
+            }
+            SwapConditionType::TargetPrice(target, _) => Some(*target),
+            SwapConditionType::PriceAbove(threshold) => Some(*threshold),
+            SwapConditionType::PriceBelow(threshold) => Some(*threshold),
+            SwapConditionType::LimitOrder(limit_price, _) => Some(*limit_price),
+            SwapConditionType::TwapAbove(threshold, _) => Some(*threshold),
+            // Rate thresholds aren't expressed in source price units
+            SwapConditionType::RateAbove(_) | SwapConditionType::RateBelow(_) => None,
+            SwapConditionType::TimeReached(_) => None,
+        }
+    }
+
+    /// Keeper ordering score: the unfilled amount, boosted by up to 2x the further `trigger_value`
+    /// is past the trigger price
+    pub fn execution_priority(&self, trigger_value: u64) -> u64 {
+        let depth_bps = match self.trigger_price() {
+            Some(trigger_price) if trigger_price > 0 => {
+                (trigger_value.abs_diff(trigger_price) as u128 * 10000 / trigger_price as u128).min(10000)
+            }
+            _ => 0,
+        };
+        let priority = self.amount_remaining as u128 * (10000 + depth_bps) / 10000;
+        priority.min(u64::MAX as u128) as u64
+    }
+
+    /// Conditions that sell once the source price rises, where the user expects to sell
+    /// above the reference price
+    pub fn is_sell_higher(&self) -> bool {
+        matches!(
+            self.condition_type,
+            SwapConditionType::PercentageIncrease(_)
+                | SwapConditionType::PriceAbove(_)
+                | SwapConditionType::TwapAbove(_, _)
+                | SwapConditionType::LimitOrder(_, OrderDirection::Sell)
+        )
+    }
+
+    /// True when a fill returning `amount_out`, against `oracle_output` implied by the oracle
+    /// at `current_price`, effectively sells below the reference price by more than `max_slippage`
+    pub fn fill_below_reference_price(&self, current_price: u64, amount_out: u64, oracle_output: u64) -> bool {
+        if oracle_output == 0 {
+            return false;
+        }
+        let effective_price = current_price as u128 * amount_out as u128 / oracle_output as u128;
+        let floor = self.reference_price as u128 * (10000 - self.max_slippage) as u128 / 10000;
+        effective_price < floor
+    }
+
+    pub fn crossing_price(&self, current_price: u64) -> Option<u64> {
+        match &self.condition_type {
+            SwapConditionType::LimitOrder(_, _) => Some(current_price),
+            _ => None,
         }
     }
 
     pub fn calculate_expected_output(&self, current_price: u64) -> u64 {
-        // Simplified calculation - in production, this would query the DEX
-        // This assumes 1:1 price ratio for demonstration
-        let base_output = (self.amount_to_swap * current_price) / self.reference_price;
+        // Oracle-based estimate scaled by the move since creation. This is only used
+        // for slippage baselines; the DEX quote in StellarDexIntegration::execute_swap
+        // is authoritative for what a swap actually returns.
+        if self.reference_price == 0 {
+            return 0;
+        }
+
+        // Widen to u128 so large amounts at large prices can't overflow
+        let base_output = (self.amount_to_swap as u128 * current_price as u128) / self.reference_price as u128;
         
         // Apply slippage protection
         let slippage_factor = 10000 - self.max_slippage; // basis points
-        (base_output * slippage_factor as u64) / 10000
+        let output = (base_output * slippage_factor as u128) / 10000;
+        output.min(u64::MAX as u128) as u64
+    }
+
+    /// Lowest price at or above `reference` plus `percentage`. Rounds up so an uneven
+    /// percentage never triggers below the exact threshold.
+    fn increase_threshold(reference: u64, percentage: u32) -> u64 {
+        let scaled = reference as u128 * (100 + percentage as u128);
+        let threshold = (scaled + 99) / 100;
+        threshold.min(u64::MAX as u128) as u64
+    }
+
+    /// Highest price at or below `reference` minus `percentage`, rounded down for the same reason
+    fn decrease_threshold(reference: u64, percentage: u32) -> u64 {
+        let remaining = 100u128.saturating_sub(percentage as u128);
+        (reference as u128 * remaining / 100) as u64
+    }
+
+    pub fn refresh_min_amount_out(&mut self, current_price: u64) {
+        // An explicit minimum from the owner is never replaced by a derived one
+        if self.min_amount_out_override.is_none() {
+            self.min_amount_out = self.calculate_expected_output(current_price);
+        }
     }
 
     pub fn update_execution(&mut self, env: &Env, execution: &SwapExecution) {
+        let current_time = env.ledger().timestamp();
+        self.last_check = current_time;
+        self.last_executed_at = Some(current_time);
+        self.retry_count = 0;
+
+        self.total_spent = self.total_spent.saturating_add(execution.amount_in);
+        self.record_fill(execution.execution_price, execution.amount_in);
+        let budget_exhausted = self.total_budget > 0 && self.total_spent >= self.total_budget;
+
+        // A partial fill keeps the order open until the remainder is swapped
+        self.amount_remaining = self.amount_remaining.saturating_sub(execution.amount_in);
+        if self.amount_remaining > 0 && !budget_exhausted {
+            return;
+        }
+
         self.execution_count += 1;
-        self.last_check = env.ledger().timestamp();
-        
-        if self.max_executions > 0 && self.execution_count >= self.max_executions {
+
+        if budget_exhausted || (self.max_executions > 0 && self.execution_count >= self.max_executions) {
             self.status = SwapStatus::Executed;
+        } else {
+            self.amount_remaining = self.amount_to_swap;
         }
     }
 
+    /// Fold a fill into the volume-weighted average and the best and worst execution prices
+    fn record_fill(&mut self, execution_price: u64, amount_in: u64) {
+        let total_filled = self.total_filled as u128 + amount_in as u128;
+        if total_filled == 0 {
+            return;
+        }
+
+        let weighted_sum = (self.average_execution_price as u128 * self.total_filled as u128)
+            .saturating_add(execution_price as u128 * amount_in as u128);
+        self.average_execution_price = (weighted_sum / total_filled).min(u64::MAX as u128) as u64;
+        self.total_filled = total_filled.min(u64::MAX as u128) as u64;
+
+        // Selling the source asset, so a higher price is the better fill
+        self.best_execution_price = Some(self.best_execution_price.map_or(execution_price, |best| best.max(execution_price)));
+        self.worst_execution_price = Some(self.worst_execution_price.map_or(execution_price, |worst| worst.min(execution_price)));
+    }
+
     pub fn cancel(&mut self) {
         self.status = SwapStatus::Cancelled;
     }
@@ -208,6 +697,14 @@ impl SwapCondition {
         self.status = SwapStatus::Failed;
     }
 
+    /// Count a retryable execution failure, failing the condition once its retries run out
+    pub fn record_retryable_failure(&mut self) {
+        self.retry_count = self.retry_count.saturating_add(1);
+        if self.retry_count > self.max_retries {
+            self.mark_as_failed();
+        }
+    }
+
     pub fn mark_as_expired(&mut self, env: &Env) {
         if env.ledger().timestamp() > self.expires_at {
             self.status = SwapStatus::Expired;
@@ -215,13 +712,19 @@ impl SwapCondition {
     }
 
     fn calculate_min_amount_out(
+        env: &Env,
         amount_in: u64,
         reference_price: u64,
         max_slippage: u32,
-    ) -> u64 {
-        let base_amount_out = (amount_in * reference_price) / reference_price; // Simplified
-        let slippage_factor = 10000 - max_slippage; // basis points
-        (base_amount_out * slippage_factor as u64) / 10000
+    ) -> Result<u64, Symbol> {
+        // Widen to u128, BTC-scale prices times large amounts overflow u64
+        let slippage_factor = 10000u128.saturating_sub(max_slippage as u128); // basis points
+        (amount_in as u128)
+            .checked_mul(reference_price as u128)
+            .and_then(|value| value.checked_div(reference_price as u128)) // Simplified
+            .and_then(|base_amount_out| base_amount_out.checked_mul(slippage_factor))
+            .and_then(|value| u64::try_from(value / 10000).ok())
+            .ok_or_else(|| Symbol::new(env, "amount_overflow"))
     }
 }
 
@@ -275,6 +778,15 @@ impl CreateSwapRequest {
             });
         }
 
+        // A delayed condition must outlive the minimum lifetime once active
+        let activates_at = current_time.saturating_add(self.activation_delay_secs);
+        if self.activation_delay_secs > 0 && self.expires_at <= activates_at.saturating_add(MIN_CONDITION_LIFETIME) {
+            return Err(SwapValidationError {
+                error_code: 2011,
+                message: Symbol::new(env, "expires_before_activation"),
+            });
+        }
+
         // Validate assets are different
         if self.source_asset == self.destination_asset {
             return Err(SwapValidationError {
@@ -283,6 +795,94 @@ impl CreateSwapRequest {
             });
         }
 
+        // Validate order splitting
+        if self.chunk_size > self.amount_to_swap
+            || (self.chunk_size > 0 && self.chunk_size < MIN_SWAP_AMOUNT)
+        {
+            return Err(SwapValidationError {
+                error_code: 2012,
+                message: Symbol::new(env, "invalid_chunk_size"),
+            });
+        }
+
+        if self.execution_interval > MAX_CONDITION_LIFETIME {
+            return Err(SwapValidationError {
+                error_code: 2013,
+                message: Symbol::new(env, "invalid_execution_interval"),
+            });
+        }
+
+        // Without a gap between fills a recurring condition could drain in back-to-back executions
+        if self.max_executions != 1 && self.execution_interval == 0 {
+            return Err(SwapValidationError {
+                error_code: 2019,
+                message: Symbol::new(env, "recurring_requires_interval"),
+            });
+        }
+
+        if self.min_confidence_override.map_or(false, |confidence| confidence > 100) {
+            return Err(SwapValidationError {
+                error_code: 2014,
+                message: Symbol::new(env, "invalid_min_confidence"),
+            });
+        }
+
+        if self.total_budget > 0 && self.total_budget < MIN_SWAP_AMOUNT {
+            return Err(SwapValidationError {
+                error_code: 2015,
+                message: Symbol::new(env, "invalid_total_budget"),
+            });
+        }
+
+        if let Some(min_amount_out) = self.min_amount_out_override {
+            if min_amount_out == 0 || min_amount_out > self.amount_to_swap.saturating_mul(MAX_MIN_OUT_MULTIPLIER) {
+                return Err(SwapValidationError {
+                    error_code: 2016,
+                    message: Symbol::new(env, "invalid_min_amount_out"),
+                });
+            }
+        }
+
+        // Symbols already cap the label at 32 characters
+        if self.label.as_ref().map_or(false, |label| *label == Symbol::new(env, "")) {
+            return Err(SwapValidationError {
+                error_code: 2018,
+                message: Symbol::new(env, "invalid_label"),
+            });
+        }
+
+        if self.max_retries > MAX_EXECUTION_RETRIES {
+            return Err(SwapValidationError {
+                error_code: 2017,
+                message: Symbol::new(env, "invalid_max_retries"),
+            });
+        }
+
+        // Validate price impact bound
+        if self.max_price_impact_bps == 0 || self.max_price_impact_bps > MAX_PRICE_IMPACT_BPS {
+            return Err(SwapValidationError {
+                error_code: 2010,
+                message: Symbol::new(env, "invalid_price_impact"),
+            });
+        }
+
+        // Validate auto-renewal settings
+        if self.auto_renew {
+            if self.renewal_period < MIN_CONDITION_LIFETIME || self.renewal_period > MAX_CONDITION_LIFETIME {
+                return Err(SwapValidationError {
+                    error_code: 2008,
+                    message: Symbol::new(env, "invalid_renewal_period"),
+                });
+            }
+
+            if self.max_renewals == 0 || self.max_renewals > MAX_RENEWALS {
+                return Err(SwapValidationError {
+                    error_code: 2009,
+                    message: Symbol::new(env, "invalid_max_renewals"),
+                });
+            }
+        }
+
         // Validate condition type
         self.validate_condition_type(env)?;
 
@@ -307,13 +907,29 @@ impl CreateSwapRequest {
                     });
                 }
             }
-            SwapConditionType::TargetPrice(price) => {
+            SwapConditionType::TargetPrice(price, tolerance_bps) => {
                 if *price == 0 {
                     return Err(SwapValidationError {
                         error_code: 2103,
                         message: Symbol::new(env, "invalid_target_price"),
                     });
                 }
+
+                if *tolerance_bps < MIN_PERCENTAGE_CHANGE || *tolerance_bps > MAX_PERCENTAGE_CHANGE {
+                    return Err(SwapValidationError {
+                        error_code: 2107,
+                        message: Symbol::new(env, "invalid_target_tolerance"),
+                    });
+                }
+
+                // Integer prices need the band to span at least one unit either side, otherwise
+                // only an exact hit on the target could trigger
+                if (*price as u128 * *tolerance_bps as u128) / 10000 == 0 {
+                    return Err(SwapValidationError {
+                        error_code: 2111,
+                        message: Symbol::new(env, "tolerance_too_tight"),
+                    });
+                }
             }
             SwapConditionType::PriceAbove(threshold) => {
                 if *threshold == 0 {
@@ -331,6 +947,52 @@ impl CreateSwapRequest {
                     });
                 }
             }
+            SwapConditionType::LimitOrder(limit_price, _) => {
+                if *limit_price == 0 {
+                    return Err(SwapValidationError {
+                        error_code: 2106,
+                        message: Symbol::new(env, "invalid_limit_price"),
+                    });
+                }
+            }
+            SwapConditionType::TwapAbove(threshold, window) => {
+                if *threshold == 0 {
+                    return Err(SwapValidationError {
+                        error_code: 2108,
+                        message: Symbol::new(env, "invalid_price_threshold"),
+                    });
+                }
+
+                if *window < MIN_CONDITION_LIFETIME || *window > MAX_CONDITION_LIFETIME {
+                    return Err(SwapValidationError {
+                        error_code: 2109,
+                        message: Symbol::new(env, "invalid_twap_window"),
+                    });
+                }
+            }
+            SwapConditionType::RateAbove(rate) | SwapConditionType::RateBelow(rate) => {
+                if *rate == 0 {
+                    return Err(SwapValidationError {
+                        error_code: 2110,
+                        message: Symbol::new(env, "invalid_exchange_rate"),
+                    });
+                }
+            }
+            SwapConditionType::TimeReached(target) => {
+                if *target <= env.ledger().timestamp() {
+                    return Err(SwapValidationError {
+                        error_code: 2112,
+                        message: Symbol::new(env, "invalid_target_time"),
+                    });
+                }
+
+                if *target > self.expires_at {
+                    return Err(SwapValidationError {
+                        error_code: 2113,
+                        message: Symbol::new(env, "target_after_expiry"),
+                    });
+                }
+            }
         }
 
         Ok(())
@@ -368,6 +1030,10 @@ impl SwapExecution {
             actual_slippage,
             gas_used,
             tx_hash,
+            crossing_price: None,
+            keeper_reward: 0,
+            protocol_fee: 0,
+            price_impact: 0,
         }
     }
 
@@ -380,6 +1046,25 @@ impl SwapExecution {
 pub struct SwapConditionManager;
 
 impl SwapConditionManager {
+    pub fn estimate_cost(lifetime: u64, execution_gas: u64) -> ConditionCostEstimate {
+        let estimated_checks = (lifetime / ASSUMED_CHECK_INTERVAL).max(1);
+        let days = (lifetime + 86399) / 86400;
+        let storage_rent = CONDITION_STORAGE_BYTES * RENT_PER_BYTE_PER_DAY * days;
+        let total_cost = CREATION_GAS_ESTIMATE
+            + CHECK_GAS_ESTIMATE * estimated_checks
+            + execution_gas
+            + storage_rent;
+
+        ConditionCostEstimate {
+            creation_gas: CREATION_GAS_ESTIMATE,
+            per_check_gas: CHECK_GAS_ESTIMATE,
+            execution_gas,
+            estimated_checks,
+            storage_rent,
+            total_cost,
+        }
+    }
+
     pub fn generate_condition_id(env: &Env, owner: &Address) -> u64 {
         let current_time = env.ledger().timestamp();
         let owner_hash = owner.to_string().len() as u64; // Simplified hash
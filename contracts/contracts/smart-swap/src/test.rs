@@ -1,7 +1,7 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, Symbol};
+use soroban_sdk::{testutils::Address as _, token, Address, Env, Symbol};
 
 fn create_test_env() -> (Env, Address, Address, Address) {
     let env = Env::default();
@@ -25,6 +25,20 @@ fn create_test_swap_request(env: &Env) -> CreateSwapRequest {
         max_slippage: 500,           // 5% slippage
         expires_at: env.ledger().timestamp() + 86400, // 24 hours
         max_executions: 1,
+        auto_renew: false,
+        renewal_period: 0,
+        max_renewals: 0,
+        max_price_impact_bps: 1000,
+        activation_delay_secs: 0,
+        chunk_size: 0,
+        execution_interval: 0,
+        min_confidence_override: None,
+        total_budget: 0,
+        min_amount_out_override: None,
+        max_retries: 3,
+        execution_mode: ExecutionMode::DexQuote,
+        label: None,
+        enforce_reference_price: false,
     }
 }
 
@@ -37,6 +51,20 @@ fn create_advanced_swap_request(env: &Env, condition_type: SwapConditionType) ->
         max_slippage: 300,            // 3% slippage
         expires_at: env.ledger().timestamp() + 3600, // 1 hour
         max_executions: 0, // Unlimited executions
+        auto_renew: false,
+        renewal_period: 0,
+        max_renewals: 0,
+        max_price_impact_bps: 1000,
+        activation_delay_secs: 0,
+        chunk_size: 0,
+        execution_interval: 300, // 5 minutes between executions
+        min_confidence_override: None,
+        total_budget: 0,
+        min_amount_out_override: None,
+        max_retries: 3,
+        execution_mode: ExecutionMode::DexQuote,
+        label: None,
+        enforce_reference_price: false,
     }
 }
 
@@ -117,7 +145,7 @@ fn test_multiple_condition_types() {
     assert!(result2.is_ok());
     
     // Test target price
-    let request3 = create_advanced_swap_request(&env, SwapConditionType::TargetPrice(150000));
+    let request3 = create_advanced_swap_request(&env, SwapConditionType::TargetPrice(150000, DEFAULT_TARGET_TOLERANCE_BPS));
     let result3 = SmartSwap::create_swap_condition(env.clone(), user.clone(), request3);
     assert!(result3.is_ok());
     
@@ -186,6 +214,31 @@ fn test_swap_condition_should_execute_logic() {
         last_check: env.ledger().timestamp(),
         execution_count: 0,
         max_executions: 1,
+        price_observations: soroban_sdk::Vec::new(&env),
+        auto_renew: false,
+        renewal_period: 0,
+        max_renewals: 0,
+        renewal_count: 0,
+        max_price_impact_bps: 1000,
+        activates_at: 0,
+        chunk_size: 0,
+        amount_remaining: 100_0000000,
+        execution_interval: 0,
+        last_executed_at: None,
+        memo: None,
+        min_confidence_override: None,
+        total_budget: 0,
+        total_spent: 0,
+        average_execution_price: 0,
+        total_filled: 0,
+        best_execution_price: None,
+        worst_execution_price: None,
+        min_amount_out_override: None,
+        retry_count: 0,
+        max_retries: 3,
+        execution_mode: ExecutionMode::DexQuote,
+        label: None,
+        enforce_reference_price: false,
     };
     
     // Should not execute at same price
@@ -210,7 +263,7 @@ fn test_swap_condition_target_price_logic() {
         owner: Address::generate(&env),
         source_asset: Symbol::new(&env, "XLM"),
         destination_asset: Symbol::new(&env, "USDC"),
-        condition_type: SwapConditionType::TargetPrice(120000), // Target price
+        condition_type: SwapConditionType::TargetPrice(120000, DEFAULT_TARGET_TOLERANCE_BPS), // Target price, 0.1% tolerance
         amount_to_swap: 100_0000000,
         min_amount_out: 90_0000000,
         max_slippage: 500,
@@ -221,6 +274,31 @@ fn test_swap_condition_target_price_logic() {
         last_check: env.ledger().timestamp(),
         execution_count: 0,
         max_executions: 1,
+        price_observations: soroban_sdk::Vec::new(&env),
+        auto_renew: false,
+        renewal_period: 0,
+        max_renewals: 0,
+        renewal_count: 0,
+        max_price_impact_bps: 1000,
+        activates_at: 0,
+        chunk_size: 0,
+        amount_remaining: 100_0000000,
+        execution_interval: 0,
+        last_executed_at: None,
+        memo: None,
+        min_confidence_override: None,
+        total_budget: 0,
+        total_spent: 0,
+        average_execution_price: 0,
+        total_filled: 0,
+        best_execution_price: None,
+        worst_execution_price: None,
+        min_amount_out_override: None,
+        retry_count: 0,
+        max_retries: 3,
+        execution_mode: ExecutionMode::DexQuote,
+        label: None,
+        enforce_reference_price: false,
     };
     
     // Should not execute far from target
@@ -231,6 +309,22 @@ fn test_swap_condition_target_price_logic() {
     assert!(condition.should_execute(120000));
     assert!(condition.should_execute(119900)); // Within 0.1% tolerance
     assert!(condition.should_execute(120100)); // Within 0.1% tolerance
+    assert!(!condition.should_execute(120200)); // Outside 0.1% tolerance
+
+    // Wider tolerance for volatile assets
+    let mut wide_condition = condition.clone();
+    wide_condition.condition_type = SwapConditionType::TargetPrice(120000, 200); // 2% tolerance
+    assert!(wide_condition.should_execute(117600));
+    assert!(wide_condition.should_execute(122400));
+    assert!(!wide_condition.should_execute(122500));
+
+    // Tolerance must fall within the percentage bounds
+    let mut request = create_test_swap_request(&env);
+    request.condition_type = SwapConditionType::TargetPrice(120000, 0);
+    assert_eq!(
+        request.validate(&env).unwrap_err().message,
+        Symbol::new(&env, "invalid_target_tolerance")
+    );
 }
 
 #[test]
@@ -265,6 +359,29 @@ fn test_add_supported_asset() {
     assert_eq!(result, Err(Symbol::new(&env, "unauthorized")));
 }
 
+#[test]
+fn test_remove_supported_asset() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let btc = Symbol::new(&env, "BTC");
+    let eth = Symbol::new(&env, "ETH");
+    assert_eq!(SmartSwap::get_supported_assets(env.clone()).len(), 0);
+
+    SmartSwap::add_supported_asset(env.clone(), admin.clone(), btc.clone()).unwrap();
+    SmartSwap::add_supported_asset(env.clone(), admin.clone(), eth.clone()).unwrap();
+    SmartSwap::add_supported_asset(env.clone(), admin.clone(), btc.clone()).unwrap();
+    assert_eq!(SmartSwap::get_supported_assets(env.clone()), Vec::from_array(&env, [btc.clone(), eth.clone()]));
+
+    let result = SmartSwap::remove_supported_asset(env.clone(), user, btc.clone());
+    assert_eq!(result, Err(Symbol::new(&env, "unauthorized")));
+
+    SmartSwap::remove_supported_asset(env.clone(), admin.clone(), btc.clone()).unwrap();
+    assert_eq!(SmartSwap::get_supported_assets(env.clone()), Vec::from_array(&env, [eth.clone()]));
+
+    // Removing an asset that isn't listed changes nothing
+    SmartSwap::remove_supported_asset(env.clone(), admin, btc).unwrap();
+    assert_eq!(SmartSwap::get_supported_assets(env.clone()), Vec::from_array(&env, [eth]));
+}
+
 #[test]
 fn test_pause_functionality() {
     let (env, admin, user, _oracle) = create_test_env();
@@ -312,6 +429,136 @@ fn test_global_stats_tracking() {
     assert_eq!(stats.active_conditions_count, 0);
 }
 
+#[test]
+fn test_verify_invariants_detects_drift() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let other_user = Address::generate(&env);
+
+    let first_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    SmartSwap::create_swap_condition(env.clone(), other_user, create_test_swap_request(&env)).unwrap();
+    SmartSwap::cancel_condition(env.clone(), user.clone(), first_id).unwrap();
+
+    let report = SmartSwap::verify_invariants(env.clone());
+    assert_eq!(report.stored_active_count, 2);
+    assert_eq!(report.actual_active_count, 2);
+    assert!(report.active_count_matches);
+    assert_eq!(report.orphaned_user_entries.len(), 0);
+
+    // Corrupt the stored stat and the user index
+    let mut stats = SmartSwap::get_global_stats(env.clone());
+    stats.active_conditions_count = 5;
+    env.storage().instance().set(&DataKey::GlobalStats, &stats);
+    let mut user_conditions = SmartSwap::get_user_conditions(env.clone(), user.clone());
+    user_conditions.push_back(999);
+    env.storage().instance().set(&DataKey::UserConditions(user), &user_conditions);
+
+    let report = SmartSwap::verify_invariants(env.clone());
+    assert_eq!(report.stored_active_count, 5);
+    assert_eq!(report.actual_active_count, 2);
+    assert!(!report.active_count_matches);
+    assert_eq!(report.orphaned_user_entries, Vec::from_array(&env, [999u64]));
+
+    // The report never repairs anything
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).active_conditions_count, 5);
+}
+
+#[test]
+fn test_cancel_all_conditions() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let other_user = Address::generate(&env);
+    assert_eq!(SmartSwap::cancel_all_conditions(env.clone(), user.clone()), Ok(0));
+
+    let mut ids = Vec::new(&env);
+    for _ in 0..4 {
+        ids.push_back(SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap());
+    }
+    let other_id = SmartSwap::create_swap_condition(env.clone(), other_user, create_test_swap_request(&env)).unwrap();
+    SmartSwap::cancel_condition(env.clone(), user.clone(), ids.get(0).unwrap()).unwrap();
+
+    // Only the three still-active conditions are cancelled
+    assert_eq!(SmartSwap::cancel_all_conditions(env.clone(), user.clone()), Ok(3));
+    for condition_id in ids.iter() {
+        assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().status, SwapStatus::Cancelled);
+    }
+
+    // Other users' conditions are untouched
+    assert_eq!(SmartSwap::get_condition(env.clone(), other_id).unwrap().status, SwapStatus::Active);
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).active_conditions_count, 1);
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), Symbol::new(&env, "XLM")), 100_0000000);
+
+    assert_eq!(SmartSwap::cancel_all_conditions(env.clone(), user), Ok(0));
+}
+
+#[test]
+fn test_condition_labels() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let ladder = Symbol::new(&env, "btc_ladder");
+    let dca = Symbol::new(&env, "dca");
+
+    let mut empty_request = create_test_swap_request(&env);
+    empty_request.label = Some(Symbol::new(&env, ""));
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), empty_request),
+        Err(Symbol::new(&env, "invalid_label"))
+    );
+
+    let mut ladder_ids = Vec::new(&env);
+    for _ in 0..2 {
+        let mut request = create_test_swap_request(&env);
+        request.label = Some(ladder.clone());
+        ladder_ids.push_back(SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap());
+    }
+    let mut dca_request = create_test_swap_request(&env);
+    dca_request.label = Some(dca.clone());
+    let dca_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), dca_request).unwrap();
+    let unlabeled_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+
+    assert_eq!(SmartSwap::get_condition(env.clone(), dca_id).unwrap().label, Some(dca.clone()));
+    assert_eq!(SmartSwap::get_condition(env.clone(), unlabeled_id).unwrap().label, None);
+
+    // Labels match regardless of status
+    SmartSwap::cancel_condition(env.clone(), user.clone(), ladder_ids.get(1).unwrap()).unwrap();
+    assert_eq!(SmartSwap::get_user_conditions_by_label(env.clone(), user.clone(), ladder), ladder_ids);
+    assert_eq!(SmartSwap::get_user_conditions_by_label(env.clone(), user.clone(), dca.clone()), Vec::from_array(&env, [dca_id]));
+
+    let other_user = Address::generate(&env);
+    assert_eq!(SmartSwap::get_user_conditions_by_label(env.clone(), other_user, dca).len(), 0);
+}
+
+#[test]
+fn test_get_all_conditions_paged() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let other_user = Address::generate(&env);
+    assert_eq!(SmartSwap::get_all_conditions_paged(env.clone(), 1, 10).len(), 0);
+
+    for index in 0..5 {
+        let owner = if index % 2 == 0 { user.clone() } else { other_user.clone() };
+        SmartSwap::create_swap_condition(env.clone(), owner, create_test_swap_request(&env)).unwrap();
+    }
+
+    // Cancel and prune condition 2 from storage
+    SmartSwap::cancel_condition(env.clone(), other_user, 2).unwrap();
+    let mut conditions: Map<u64, SwapCondition> = env.storage().instance().get(&DataKey::SwapConditions).unwrap();
+    conditions.remove(2);
+    env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+    let page = SmartSwap::get_all_conditions_paged(env.clone(), 1, 3);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(0).unwrap().id, 1);
+    assert_eq!(page.get(1).unwrap().id, 3);
+
+    let page = SmartSwap::get_all_conditions_paged(env.clone(), 4, 3);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(0).unwrap().id, 4);
+    assert_eq!(page.get(1).unwrap().id, 5);
+
+    // Start 0 is treated as the first ID, and pages past the end are empty
+    assert_eq!(SmartSwap::get_all_conditions_paged(env.clone(), 0, 10).len(), 4);
+    assert_eq!(SmartSwap::get_all_conditions_paged(env.clone(), 6, 10).len(), 0);
+    assert_eq!(SmartSwap::get_all_conditions_paged(env.clone(), u32::MAX, u32::MAX).len(), 0);
+}
+
 #[test]
 fn test_user_condition_limit() {
     let (env, admin, user, _oracle) = create_test_env();
@@ -324,9 +571,18 @@ fn test_user_condition_limit() {
         admin: admin.clone(),
         oracle_config,
         dex_config,
-        paused: false,
+        pause_flags: PauseFlags::all(false),
         max_conditions_per_user: 2, // Set low limit
         min_condition_value: 10_0000000,
+        enforce_asset_whitelist: false,
+        keeper_reward_bps: DEFAULT_KEEPER_REWARD_BPS,
+        swap_deadline_secs: DEFAULT_SWAP_DEADLINE_SECS,
+        max_deviation_bps: DEFAULT_MAX_DEVIATION_BPS,
+        protocol_fee_bps: DEFAULT_PROTOCOL_FEE_BPS,
+        max_pending_per_asset: 0,
+        min_check_interval_secs: 0,
+        max_total_active_conditions: DEFAULT_MAX_TOTAL_ACTIVE_CONDITIONS,
+        max_oracle_dex_divergence_bps: 0,
     };
     
     env.storage().instance().set(&DataKey::Admin, &config);
@@ -346,6 +602,38 @@ fn test_user_condition_limit() {
     assert_eq!(result3, Err(Symbol::new(&env, "condition_limit_exceeded")));
 }
 
+#[test]
+fn test_global_condition_cap() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let other_user = Address::generate(&env);
+
+    assert_eq!(
+        SmartSwap::set_max_total_active_conditions(env.clone(), admin.clone(), 0),
+        Err(Symbol::new(&env, "invalid_condition_cap"))
+    );
+    assert_eq!(
+        SmartSwap::set_max_total_active_conditions(env.clone(), user.clone(), 2),
+        Err(Symbol::new(&env, "unauthorized"))
+    );
+    SmartSwap::set_max_total_active_conditions(env.clone(), admin, 2).unwrap();
+
+    // The cap counts conditions across all users
+    let first_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    SmartSwap::create_swap_condition(env.clone(), other_user.clone(), create_test_swap_request(&env)).unwrap();
+
+    let result = SmartSwap::create_swap_condition(env.clone(), other_user.clone(), create_test_swap_request(&env));
+    assert_eq!(result, Err(Symbol::new(&env, "global_condition_cap_reached")));
+    assert_eq!(
+        SmartSwap::validate_request(env.clone(), create_test_swap_request(&env)),
+        Err(Symbol::new(&env, "global_condition_cap_reached"))
+    );
+
+    // Cancelling frees a slot
+    SmartSwap::cancel_condition(env.clone(), user, first_id).unwrap();
+    assert!(SmartSwap::create_swap_condition(env.clone(), other_user, create_test_swap_request(&env)).is_ok());
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).active_conditions_count, 2);
+}
+
 #[test]
 fn test_cleanup_expired_conditions() {
     let (env, _admin, user, _oracle) = create_test_env();
@@ -398,6 +686,8 @@ fn test_exchange_rate_calculation() {
         &oracle_config,
         Symbol::new(&env, "XLM"),
         Symbol::new(&env, "USDC"),
+        DEFAULT_ASSET_DECIMALS,
+        DEFAULT_ASSET_DECIMALS,
     );
     
     assert!(result.is_ok());
@@ -484,6 +774,31 @@ fn test_swap_condition_validation() {
         last_check: current_time,
         execution_count: 0,
         max_executions: 1,
+        price_observations: soroban_sdk::Vec::new(&env),
+        auto_renew: false,
+        renewal_period: 0,
+        max_renewals: 0,
+        renewal_count: 0,
+        max_price_impact_bps: 1000,
+        activates_at: 0,
+        chunk_size: 0,
+        amount_remaining: 100_0000000,
+        execution_interval: 0,
+        last_executed_at: None,
+        memo: None,
+        min_confidence_override: None,
+        total_budget: 0,
+        total_spent: 0,
+        average_execution_price: 0,
+        total_filled: 0,
+        best_execution_price: None,
+        worst_execution_price: None,
+        min_amount_out_override: None,
+        retry_count: 0,
+        max_retries: 3,
+        execution_mode: ExecutionMode::DexQuote,
+        label: None,
+        enforce_reference_price: false,
     };
     
     assert!(valid_condition.is_valid(&env).is_ok());
@@ -514,6 +829,20 @@ fn test_create_swap_request_validation() {
         max_slippage: 500,
         expires_at: env.ledger().timestamp() + 3600,
         max_executions: 1,
+        auto_renew: false,
+        renewal_period: 0,
+        max_renewals: 0,
+        max_price_impact_bps: 1000,
+        activation_delay_secs: 0,
+        chunk_size: 0,
+        execution_interval: 0,
+        min_confidence_override: None,
+        total_budget: 0,
+        min_amount_out_override: None,
+        max_retries: 3,
+        execution_mode: ExecutionMode::DexQuote,
+        label: None,
+        enforce_reference_price: false,
     };
     
     assert!(valid_request.validate(&env).is_ok());
@@ -529,4 +858,3588 @@ fn test_create_swap_request_validation() {
     invalid_request.destination_asset = invalid_request.source_asset.clone();
     
     assert!(invalid_request.validate(&env).is_err());
-}
\ No newline at end of file
+}
+#[test]
+fn test_condition_owner_index() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let request = create_test_swap_request(&env);
+
+    // Unknown conditions have no owner
+    assert_eq!(SmartSwap::get_condition_owner(env.clone(), 1), None);
+
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    // Owner index should match the stored condition
+    let owner = SmartSwap::get_condition_owner(env.clone(), condition_id);
+    assert_eq!(owner, Some(user.clone()));
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().owner, user);
+}
+
+// USDC -> ETH routes through a deep default pool, so it clears 5% slippage protection
+fn create_executable_swap_request(env: &Env, condition_type: SwapConditionType) -> CreateSwapRequest {
+    CreateSwapRequest {
+        source_asset: Symbol::new(env, "USDC"),
+        destination_asset: Symbol::new(env, "ETH"),
+        condition_type,
+        amount_to_swap: 100_0000000, // 100 USDC
+        max_slippage: 500,           // 5% slippage
+        expires_at: env.ledger().timestamp() + 86400, // 24 hours
+        max_executions: 1,
+        auto_renew: false,
+        renewal_period: 0,
+        max_renewals: 0,
+        max_price_impact_bps: 1000,
+        activation_delay_secs: 0,
+        chunk_size: 0,
+        execution_interval: 0,
+        min_confidence_override: None,
+        total_budget: 0,
+        min_amount_out_override: None,
+        max_retries: 3,
+        execution_mode: ExecutionMode::DexQuote,
+        label: None,
+        enforce_reference_price: false,
+    }
+}
+
+fn create_test_condition(env: &Env, condition_type: SwapConditionType) -> SwapCondition {
+    SwapCondition {
+        id: 1,
+        owner: Address::generate(env),
+        source_asset: Symbol::new(env, "XLM"),
+        destination_asset: Symbol::new(env, "USDC"),
+        condition_type,
+        amount_to_swap: 100_0000000,
+        min_amount_out: 90_0000000,
+        max_slippage: 500,
+        reference_price: 100000,
+        created_at: env.ledger().timestamp(),
+        expires_at: env.ledger().timestamp() + 3600,
+        status: SwapStatus::Active,
+        last_check: env.ledger().timestamp(),
+        execution_count: 0,
+        max_executions: 1,
+        price_observations: soroban_sdk::Vec::new(env),
+        auto_renew: false,
+        renewal_period: 0,
+        max_renewals: 0,
+        renewal_count: 0,
+        max_price_impact_bps: 1000,
+        activates_at: 0,
+        chunk_size: 0,
+        amount_remaining: 100_0000000,
+        execution_interval: 0,
+        last_executed_at: None,
+        memo: None,
+        min_confidence_override: None,
+        total_budget: 0,
+        total_spent: 0,
+        average_execution_price: 0,
+        total_filled: 0,
+        best_execution_price: None,
+        worst_execution_price: None,
+        min_amount_out_override: None,
+        retry_count: 0,
+        max_retries: 3,
+        execution_mode: ExecutionMode::DexQuote,
+        label: None,
+        enforce_reference_price: false,
+    }
+}
+
+#[test]
+fn test_limit_order_buy_logic() {
+    let env = Env::default();
+    let condition = create_test_condition(
+        &env,
+        SwapConditionType::LimitOrder(120000, OrderDirection::Buy),
+    );
+
+    // Buy fires at or below the limit price
+    assert!(!condition.should_execute(120001));
+    assert!(condition.should_execute(120000)); // Exact boundary
+    assert!(condition.should_execute(110000));
+
+    assert_eq!(condition.crossing_price(119000), Some(119000));
+}
+
+#[test]
+fn test_limit_order_sell_logic() {
+    let env = Env::default();
+    let condition = create_test_condition(
+        &env,
+        SwapConditionType::LimitOrder(120000, OrderDirection::Sell),
+    );
+
+    // Sell fires at or above the limit price
+    assert!(!condition.should_execute(119999));
+    assert!(condition.should_execute(120000)); // Exact boundary
+    assert!(condition.should_execute(130000));
+
+    // Non-limit conditions don't record a crossing price
+    let other = create_test_condition(&env, SwapConditionType::PriceAbove(120000));
+    assert_eq!(other.crossing_price(130000), None);
+}
+
+#[test]
+fn test_limit_order_validation() {
+    let env = Env::default();
+    let mut request = create_test_swap_request(&env);
+
+    request.condition_type = SwapConditionType::LimitOrder(150000, OrderDirection::Sell);
+    assert!(request.validate(&env).is_ok());
+
+    request.condition_type = SwapConditionType::LimitOrder(0, OrderDirection::Buy);
+    let error = request.validate(&env).unwrap_err();
+    assert_eq!(error.message, Symbol::new(&env, "invalid_limit_price"));
+}
+
+#[test]
+fn test_twap_sample_buffer_is_bounded() {
+    let env = Env::default();
+    let mut oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+    oracle_config.twap_max_samples = 4;
+    let xlm = Symbol::new(&env, "XLM");
+    assert_eq!(SmartSwap::get_price_twap(env.clone(), xlm.clone()), Err(Symbol::new(&env, "no_price_samples")));
+
+    // Push well past the cap
+    for i in 0..10u64 {
+        let mut price_data = create_test_price_data(&env, 100 + i);
+        price_data.timestamp = i * 60;
+        SmartSwap::record_price_sample(&env, &oracle_config, &price_data);
+    }
+
+    // Only the newest four samples are retained
+    let history = SmartSwap::get_price_history(env.clone(), xlm.clone());
+    assert_eq!(history.len(), 4);
+    assert_eq!(history.get(0).unwrap().price, 106);
+    assert_eq!(history.get(3).unwrap().price, 109);
+
+    // TWAP covers the retained window only: prices 106, 107, 108 each held for 60s
+    assert_eq!(SmartSwap::get_price_twap(env.clone(), xlm), Ok(107));
+}
+
+#[test]
+fn test_twap_max_samples_validation() {
+    let env = Env::default();
+    let mut oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+    assert_eq!(oracle_config.twap_max_samples, DEFAULT_TWAP_MAX_SAMPLES);
+    assert!(OracleConfigManager::validate_config(&env, &oracle_config).is_ok());
+
+    oracle_config.twap_max_samples = 1;
+    assert_eq!(
+        OracleConfigManager::validate_config(&env, &oracle_config),
+        Err(Symbol::new(&env, "invalid_twap_max_samples"))
+    );
+
+    oracle_config.twap_max_samples = MAX_TWAP_SAMPLES + 1;
+    assert_eq!(
+        OracleConfigManager::validate_config(&env, &oracle_config),
+        Err(Symbol::new(&env, "invalid_twap_max_samples"))
+    );
+}
+
+#[test]
+fn test_two_step_admin_transfer() {
+    let (env, admin, _user, _oracle) = create_test_env();
+    let new_admin = Address::generate(&env);
+
+    SmartSwap::propose_admin(env.clone(), admin.clone(), new_admin.clone()).unwrap();
+
+    // Proposal alone doesn't change the admin
+    let result = SmartSwap::add_supported_asset(env.clone(), new_admin.clone(), Symbol::new(&env, "BTC"));
+    assert_eq!(result, Err(Symbol::new(&env, "unauthorized")));
+
+    // Only the pending admin can accept
+    let result = SmartSwap::accept_admin(env.clone(), admin.clone());
+    assert_eq!(result, Err(Symbol::new(&env, "not_pending_admin")));
+
+    SmartSwap::accept_admin(env.clone(), new_admin.clone()).unwrap();
+
+    // New admin has control, old admin doesn't
+    assert!(SmartSwap::add_supported_asset(env.clone(), new_admin, Symbol::new(&env, "BTC")).is_ok());
+    let result = SmartSwap::add_supported_asset(env.clone(), admin, Symbol::new(&env, "ETH"));
+    assert_eq!(result, Err(Symbol::new(&env, "unauthorized")));
+}
+
+#[test]
+fn test_propose_admin_unauthorized() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let result = SmartSwap::propose_admin(env.clone(), user.clone(), user);
+    assert_eq!(result, Err(Symbol::new(&env, "unauthorized")));
+}
+
+#[test]
+fn test_accept_admin_without_proposal() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let result = SmartSwap::accept_admin(env.clone(), user);
+    assert_eq!(result, Err(Symbol::new(&env, "no_pending_admin")));
+}
+
+#[test]
+fn test_is_paused_view() {
+    let (env, admin, _user, _oracle) = create_test_env();
+
+    assert!(!SmartSwap::is_paused(env.clone()));
+
+    SmartSwap::set_pause_status(env.clone(), admin.clone(), true).unwrap();
+    assert!(SmartSwap::is_paused(env.clone()));
+
+    // Any single paused operation counts, and the cache tracks the config
+    let mut flags = PauseFlags::all(false);
+    flags.cancel_paused = true;
+    SmartSwap::set_pause_flags(env.clone(), admin.clone(), flags.clone()).unwrap();
+    assert!(SmartSwap::is_paused(env.clone()));
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    assert_eq!(config.pause_flags, flags);
+
+    SmartSwap::set_pause_status(env.clone(), admin, false).unwrap();
+    assert!(!SmartSwap::is_paused(env.clone()));
+}
+
+#[test]
+fn test_create_with_btc_scale_reference_price() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    // 50 BTC at the mock 45000000000 price overflows u64 when multiplied out
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.source_asset = Symbol::new(&env, "BTC");
+    request.amount_to_swap = 50_0000000;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.reference_price, 45000000000);
+    assert_eq!(condition.min_amount_out, 47_5000000); // 5% slippage
+}
+
+#[test]
+fn test_refresh_user_min_outputs() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let first_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    let second_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    SmartSwap::cancel_condition(env.clone(), user.clone(), second_id).unwrap();
+
+    // Simulate the market doubling since the condition was created
+    let mut conditions: Map<u64, SwapCondition> = env.storage().instance().get(&DataKey::SwapConditions).unwrap();
+    let mut condition = conditions.get(first_id).unwrap();
+    condition.reference_price = 60000; // Mock XLM oracle price is 120000
+    conditions.set(first_id, condition);
+    env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+    let updated = SmartSwap::refresh_user_min_outputs(env.clone(), user.clone()).unwrap();
+    assert_eq!(updated, 1); // Cancelled condition is skipped
+
+    // 100 XLM * 2 with 5% slippage
+    let condition = SmartSwap::get_condition(env.clone(), first_id).unwrap();
+    assert_eq!(condition.min_amount_out, 190_0000000);
+}
+
+#[test]
+fn test_percentage_math_does_not_overflow() {
+    let env = Env::default();
+
+    // BTC-scale reference price where reference * percentage exceeds u64
+    let mut condition = create_test_condition(&env, SwapConditionType::PercentageIncrease(MAX_PERCENTAGE_CHANGE));
+    condition.reference_price = u64::MAX / 50;
+    assert!(!condition.should_execute(u64::MAX / 50));
+    assert!(condition.should_execute(u64::MAX));
+
+    condition.condition_type = SwapConditionType::PercentageDecrease(MAX_PERCENTAGE_CHANGE);
+    assert!(condition.should_execute(0));
+
+    // Large amount at a large price saturates rather than panicking
+    condition.amount_to_swap = 1_000_000_0000000; // 1M XLM
+    condition.reference_price = 1;
+    assert_eq!(condition.calculate_expected_output(45000000000), u64::MAX);
+}
+
+#[test]
+fn test_expected_output_with_zero_reference_price() {
+    let env = Env::default();
+    let mut condition = create_test_condition(&env, SwapConditionType::PriceAbove(100000));
+    condition.reference_price = 0;
+
+    assert_eq!(condition.calculate_expected_output(120000), 0);
+}
+
+#[test]
+fn test_get_user_conditions_by_status() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let first_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    let second_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    let third_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    SmartSwap::cancel_condition(env.clone(), user.clone(), second_id).unwrap();
+
+    // Add a dangling ID that points at nothing
+    let mut user_conditions = SmartSwap::get_user_conditions(env.clone(), user.clone());
+    user_conditions.push_back(999);
+    env.storage().instance().set(&DataKey::UserConditions(user.clone()), &user_conditions);
+
+    let active = SmartSwap::get_user_conditions_by_status(env.clone(), user.clone(), SwapStatus::Active);
+    assert_eq!(active.len(), 2);
+    assert_eq!(active.get(0).unwrap().id, first_id);
+    assert_eq!(active.get(1).unwrap().id, third_id);
+
+    let cancelled = SmartSwap::get_user_conditions_by_status(env.clone(), user, SwapStatus::Cancelled);
+    assert_eq!(cancelled.len(), 1);
+    assert_eq!(cancelled.get(0).unwrap().id, second_id);
+}
+
+#[test]
+fn test_get_user_assets() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    // XLM -> USDC and XLM -> BTC overlap on XLM
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    let request = create_advanced_swap_request(&env, SwapConditionType::PriceAbove(200000));
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    let assets = SmartSwap::get_user_assets(env.clone(), user.clone());
+    assert_eq!(assets.len(), 3);
+    assert!(assets.contains(&Symbol::new(&env, "XLM")));
+    assert!(assets.contains(&Symbol::new(&env, "USDC")));
+    assert!(assets.contains(&Symbol::new(&env, "BTC")));
+
+    // Cancelled conditions no longer contribute their assets
+    SmartSwap::cancel_condition(env.clone(), user.clone(), 2).unwrap();
+    let assets = SmartSwap::get_user_assets(env.clone(), user);
+    assert_eq!(assets.len(), 2);
+    assert!(!assets.contains(&Symbol::new(&env, "BTC")));
+}
+
+#[test]
+fn test_compact_user_conditions() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let first_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    let second_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+
+    let mut request = create_test_swap_request(&env);
+    request.expires_at = env.ledger().timestamp() + MIN_CONDITION_LIFETIME;
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    // Index shrinks after cancellation
+    SmartSwap::cancel_condition(env.clone(), user.clone(), first_id).unwrap();
+    assert_eq!(SmartSwap::compact_user_conditions(env.clone(), user.clone()).unwrap(), 1);
+    assert_eq!(SmartSwap::get_user_conditions(env.clone(), user.clone()).len(), 2);
+
+    // Index shrinks after expiry cleanup
+    env.ledger().with_mut(|li| {
+        li.timestamp += MIN_CONDITION_LIFETIME + 1;
+    });
+    SmartSwap::cleanup_expired_conditions(env.clone(), 10);
+    assert_eq!(SmartSwap::compact_user_conditions(env.clone(), user.clone()).unwrap(), 1);
+
+    let user_conditions = SmartSwap::get_user_conditions(env.clone(), user);
+    assert_eq!(user_conditions.len(), 1);
+    assert_eq!(user_conditions.get(0).unwrap(), second_id);
+}
+
+#[test]
+fn test_slippage_blocked_trigger_stays_active() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    // Trigger is met immediately, but the XLM/USDC pool can't meet min_amount_out
+    let mut request = create_test_swap_request(&env);
+    request.condition_type = SwapConditionType::PriceAbove(1);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    let keeper = Address::generate(&env);
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id);
+    assert_eq!(result, Ok(None));
+
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.status, SwapStatus::Active);
+    assert_eq!(condition.execution_count, 0);
+}
+
+fn create_test_price_data(env: &Env, price: u64) -> PriceData {
+    PriceData {
+        asset_symbol: Symbol::new(env, "XLM"),
+        price,
+        timestamp: env.ledger().timestamp(),
+        confidence: 85,
+        source_count: 5,
+        is_fallback: false,
+    }
+}
+
+#[test]
+fn test_fallback_price_thresholds() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    env.ledger().with_mut(|li| li.timestamp += 1000);
+
+    // Live data at 85% confidence fails the primary bar, the 5 minute old fallback at 70% is served
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let mut oracle_config = config.oracle_config.clone();
+    oracle_config.max_price_age = 120;
+    oracle_config.min_confidence = 90;
+    let result = PriceOracleClient::get_price(&env, &oracle_config, Symbol::new(&env, "USDC"));
+    assert!(result.success);
+    let fallback = result.price_data.unwrap();
+    assert!(fallback.is_fallback);
+    assert_eq!(PriceOracleClient::validate_price_for_swap(&env, &fallback, &oracle_config), Ok(()));
+
+    // The same data checked as a live price fails on age
+    let mut as_primary = fallback.clone();
+    as_primary.is_fallback = false;
+    assert_eq!(
+        PriceOracleClient::validate_price_for_swap(&env, &as_primary, &oracle_config),
+        Err(Symbol::new(&env, "price_too_old"))
+    );
+
+    // Conditions keep executing on the fallback
+    SmartSwap::update_oracle_config(env.clone(), admin.clone(), oracle_config.clone(), true).unwrap();
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.success);
+
+    // Fallback thresholds apply on their own
+    let mut strict_fallback = oracle_config.clone();
+    strict_fallback.min_fallback_confidence = 80;
+    let result = PriceOracleClient::get_price(&env, &strict_fallback, Symbol::new(&env, "USDC"));
+    assert!(!result.success);
+    assert_eq!(result.error_message, Some(Symbol::new(&env, "invalid_fallback_price")));
+
+    let mut invalid_config = oracle_config;
+    invalid_config.max_fallback_price_age = 0;
+    assert_eq!(
+        SmartSwap::update_oracle_config(env.clone(), admin, invalid_config, true),
+        Err(Symbol::new(&env, "invalid_max_fallback_price_age"))
+    );
+}
+
+#[test]
+fn test_multi_oracle_median_aggregation() {
+    let env = Env::default();
+    let mut oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+    oracle_config.oracle_addresses.push_back(Address::generate(&env));
+    oracle_config.oracle_addresses.push_back(Address::generate(&env));
+
+    // Three sources, one wildly off
+    let mut responses = soroban_sdk::Vec::new(&env);
+    responses.push_back(create_test_price_data(&env, 120000));
+    responses.push_back(create_test_price_data(&env, 9_000000));
+    responses.push_back(create_test_price_data(&env, 121000));
+
+    let result = PriceOracleClient::aggregate_price_responses(
+        &env,
+        &oracle_config,
+        Symbol::new(&env, "XLM"),
+        &responses,
+    );
+
+    assert!(result.success);
+    let price_data = result.price_data.unwrap();
+    assert_eq!(price_data.price, 121000);
+    assert_eq!(price_data.source_count, 3);
+}
+
+#[test]
+fn test_multi_oracle_quorum_not_met() {
+    let env = Env::default();
+    let mut oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+    oracle_config.oracle_addresses.push_back(Address::generate(&env));
+    oracle_config.oracle_addresses.push_back(Address::generate(&env));
+    oracle_config.fallback_enabled = false;
+
+    // Only one of three responses passes the confidence filter
+    let mut low_confidence = create_test_price_data(&env, 120000);
+    low_confidence.confidence = 10;
+    let mut responses = soroban_sdk::Vec::new(&env);
+    responses.push_back(create_test_price_data(&env, 120000));
+    responses.push_back(low_confidence.clone());
+    responses.push_back(low_confidence);
+
+    let result = PriceOracleClient::aggregate_price_responses(
+        &env,
+        &oracle_config,
+        Symbol::new(&env, "XLM"),
+        &responses,
+    );
+
+    assert!(!result.success);
+    assert_eq!(result.error_message, Some(Symbol::new(&env, "insufficient_oracle_quorum")));
+}
+
+#[test]
+fn test_get_status_counts() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let first_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+
+    let mut request = create_test_swap_request(&env);
+    request.expires_at = env.ledger().timestamp() + MIN_CONDITION_LIFETIME;
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    SmartSwap::cancel_condition(env.clone(), user, first_id).unwrap();
+
+    env.ledger().with_mut(|li| {
+        li.timestamp += MIN_CONDITION_LIFETIME + 1;
+    });
+    SmartSwap::cleanup_expired_conditions(env.clone(), 10);
+
+    let counts = SmartSwap::get_status_counts(env.clone());
+    assert_eq!(counts.active, 1);
+    assert_eq!(counts.cancelled, 1);
+    assert_eq!(counts.expired, 1);
+    assert_eq!(counts.executed, 0);
+    assert_eq!(counts.failed, 0);
+}
+
+#[test]
+fn test_dual_oracle_agreeing_prices() {
+    let env = Env::default();
+    let mut oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+    oracle_config.secondary_oracle_address = Some(Address::generate(&env));
+
+    let primary = create_test_price_data(&env, 120000);
+    let secondary = create_test_price_data(&env, 121000);
+
+    let result = PriceOracleClient::combine_dual_prices(&env, &oracle_config, &primary, &secondary);
+    assert!(result.success);
+    assert_eq!(result.price_data.unwrap().price, 120500);
+
+    // Full lookup with both oracles configured
+    let result = PriceOracleClient::get_price(&env, &oracle_config, Symbol::new(&env, "XLM"));
+    assert!(result.success);
+}
+
+#[test]
+fn test_dual_oracle_divergent_prices_rejected() {
+    let env = Env::default();
+    let mut oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+    oracle_config.secondary_oracle_address = Some(Address::generate(&env));
+
+    // 10% apart, default threshold is 2%
+    let primary = create_test_price_data(&env, 120000);
+    let secondary = create_test_price_data(&env, 132000);
+
+    let result = PriceOracleClient::combine_dual_prices(&env, &oracle_config, &primary, &secondary);
+    assert!(!result.success);
+    assert_eq!(result.error_message, Some(Symbol::new(&env, "oracle_divergence")));
+}
+
+#[test]
+fn test_secondary_oracle_conflicts_with_aggregation() {
+    let env = Env::default();
+    let mut oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+    oracle_config.secondary_oracle_address = Some(Address::generate(&env));
+    assert_eq!(OracleConfigManager::validate_config(&env, &oracle_config), Ok(()));
+
+    oracle_config.oracle_addresses.push_back(Address::generate(&env));
+    assert_eq!(
+        OracleConfigManager::validate_config(&env, &oracle_config),
+        Err(Symbol::new(&env, "conflicting_oracle_sources"))
+    );
+
+    oracle_config.secondary_oracle_address = None;
+    assert_eq!(OracleConfigManager::validate_config(&env, &oracle_config), Ok(()));
+}
+
+#[test]
+fn test_asset_whitelist_enforcement() {
+    let (env, admin, user, _oracle) = create_test_env();
+    SmartSwap::add_supported_asset(env.clone(), admin.clone(), Symbol::new(&env, "XLM")).unwrap();
+    SmartSwap::add_supported_asset(env.clone(), admin.clone(), Symbol::new(&env, "USDC")).unwrap();
+
+    // Whitelist disabled: unlisted BTC destination is allowed
+    let request = create_advanced_swap_request(&env, SwapConditionType::PriceAbove(200000));
+    assert!(SmartSwap::create_swap_condition(env.clone(), user.clone(), request).is_ok());
+
+    SmartSwap::set_asset_whitelist_enforcement(env.clone(), admin, true).unwrap();
+
+    // Allowed pair
+    let request = create_test_swap_request(&env);
+    assert!(SmartSwap::create_swap_condition(env.clone(), user.clone(), request).is_ok());
+
+    // Disallowed source
+    let mut request = create_test_swap_request(&env);
+    request.source_asset = Symbol::new(&env, "ETH");
+    let result = SmartSwap::create_swap_condition(env.clone(), user, request);
+    assert_eq!(result, Err(Symbol::new(&env, "unsupported_asset")));
+
+    let result = SmartSwap::get_swap_quote(
+        env.clone(),
+        Symbol::new(&env, "ETH"),
+        Symbol::new(&env, "USDC"),
+        100_0000000,
+    );
+    assert_eq!(result, Err(Symbol::new(&env, "unsupported_asset")));
+}
+
+#[test]
+fn test_keeper_reward_only_on_execution() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    // Mock USDC price is 1000000, so this never triggers
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(2000000));
+    let idle_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), idle_id).unwrap();
+    assert!(result.is_none());
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).total_keeper_rewards, 0);
+
+    // Triggers immediately
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let ready_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, ready_id)
+        .unwrap()
+        .unwrap();
+
+    // 0.1% of 100 USDC
+    assert_eq!(execution.keeper_reward, 1000000);
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).total_keeper_rewards, 1000000);
+}
+
+#[test]
+fn test_estimate_output_at_trigger() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    // Target 10% above the mock USDC price
+    let request = create_executable_swap_request(&env, SwapConditionType::TargetPrice(1100000, 50));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    let quote = SmartSwap::get_swap_quote(
+        env.clone(),
+        Symbol::new(&env, "USDC"),
+        Symbol::new(&env, "ETH"),
+        100_0000000,
+    )
+    .unwrap();
+
+    let estimate = SmartSwap::estimate_output_at_trigger(env.clone(), condition_id).unwrap();
+    assert_eq!(estimate, quote.amount_out * 11 / 10);
+
+    let result = SmartSwap::estimate_output_at_trigger(env.clone(), 999);
+    assert_eq!(result, Err(Symbol::new(&env, "condition_not_found")));
+}
+
+#[test]
+fn test_amount_too_small_after_fee() {
+    let env = Env::default();
+    let mut dex_config = DexConfigManager::create_default_config(&env, Address::generate(&env));
+    dex_config.fee_tier = MAX_FEE_TIER;
+
+    // 1 stroop at a 10% fee rounds down to nothing
+    let result = StellarDexIntegration::get_swap_quote(
+        &env,
+        &dex_config,
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "USDC"),
+        1,
+    );
+
+    assert_eq!(result, Err(Symbol::new(&env, "amount_too_small_after_fee")));
+}
+
+#[test]
+fn test_pause_flags_create_only() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+
+    let flags = PauseFlags { create_paused: true, execute_paused: false, cancel_paused: false };
+    SmartSwap::set_pause_flags(env.clone(), admin, flags.clone()).unwrap();
+    assert_eq!(SmartSwap::get_pause_flags(env.clone()), flags);
+    assert!(SmartSwap::is_paused(env.clone()));
+
+    // New conditions are blocked, existing ones can still wind down
+    let result = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env));
+    assert_eq!(result, Err(Symbol::new(&env, "contract_paused")));
+    assert!(SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id).is_ok());
+    assert!(SmartSwap::cancel_condition(env.clone(), user, condition_id).is_ok());
+}
+
+#[test]
+fn test_pause_flags_execute_only() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+
+    let flags = PauseFlags { create_paused: false, execute_paused: true, cancel_paused: false };
+    SmartSwap::set_pause_flags(env.clone(), admin, flags).unwrap();
+
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id);
+    assert_eq!(result, Err(Symbol::new(&env, "contract_paused")));
+    assert!(SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).is_ok());
+    assert!(SmartSwap::cancel_condition(env.clone(), user, condition_id).is_ok());
+}
+
+#[test]
+fn test_pause_flags_cancel_only() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+
+    let flags = PauseFlags { create_paused: false, execute_paused: false, cancel_paused: true };
+    SmartSwap::set_pause_flags(env.clone(), admin, flags).unwrap();
+
+    let result = SmartSwap::cancel_condition(env.clone(), user.clone(), condition_id);
+    assert_eq!(result, Err(Symbol::new(&env, "contract_paused")));
+    assert!(SmartSwap::create_swap_condition(env.clone(), user, create_test_swap_request(&env)).is_ok());
+    assert!(SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id).is_ok());
+}
+
+#[test]
+fn test_set_pause_status_sets_all_flags() {
+    let (env, admin, _user, _oracle) = create_test_env();
+
+    SmartSwap::set_pause_status(env.clone(), admin.clone(), true).unwrap();
+    assert_eq!(SmartSwap::get_pause_flags(env.clone()), PauseFlags::all(true));
+
+    SmartSwap::set_pause_status(env.clone(), admin, false).unwrap();
+    assert_eq!(SmartSwap::get_pause_flags(env.clone()), PauseFlags::all(false));
+    assert!(!SmartSwap::is_paused(env.clone()));
+}
+
+#[test]
+fn test_twap_condition_ignores_spike() {
+    let env = Env::default();
+    let mut condition = create_test_condition(&env, SwapConditionType::TwapAbove(150000, 600));
+
+    // A single spike, then normal prices every minute
+    condition.record_observation(0, 300000);
+    assert!(!condition.should_execute(300000)); // Spike alone does not fire
+    for i in 1..=10u64 {
+        condition.record_observation(i * 60, 100000);
+        assert!(!condition.should_execute(100000));
+    }
+
+    // Spike held 60s of a 600s window
+    assert_eq!(condition.calculate_twap(600), Some(120000));
+    assert!(condition.price_observations.len() <= MAX_TWAP_OBSERVATIONS);
+}
+
+#[test]
+fn test_twap_condition_fires_on_sustained_move() {
+    let env = Env::default();
+    let mut condition = create_test_condition(&env, SwapConditionType::TwapAbove(150000, 600));
+
+    for i in 0..=10u64 {
+        condition.record_observation(i * 60, 200000);
+    }
+
+    assert!(condition.should_execute(200000));
+
+    // Buffer stays bounded with frequent checks
+    for i in 0..100u64 {
+        condition.record_observation(600 + i, 200000);
+    }
+    assert!(condition.price_observations.len() <= MAX_TWAP_OBSERVATIONS);
+}
+
+#[test]
+fn test_estimate_condition_cost() {
+    let (env, _admin, _user, _oracle) = create_test_env();
+
+    let mut short_request = create_test_swap_request(&env);
+    short_request.expires_at = env.ledger().timestamp() + 3600; // 1 hour
+    let mut long_request = create_test_swap_request(&env);
+    long_request.expires_at = env.ledger().timestamp() + 86400 * 30; // 30 days
+
+    let short_estimate = SmartSwap::estimate_condition_cost(env.clone(), short_request).unwrap();
+    let long_estimate = SmartSwap::estimate_condition_cost(env.clone(), long_request).unwrap();
+
+    // Same swap, so creation and per-check costs match
+    assert_eq!(short_estimate.creation_gas, long_estimate.creation_gas);
+    assert_eq!(short_estimate.per_check_gas, long_estimate.per_check_gas);
+    assert_eq!(short_estimate.execution_gas, long_estimate.execution_gas);
+
+    // Longer lifetime means more checks and more rent
+    assert!(long_estimate.estimated_checks > short_estimate.estimated_checks);
+    assert!(long_estimate.storage_rent > short_estimate.storage_rent);
+    assert!(long_estimate.total_cost > short_estimate.total_cost);
+}
+
+#[test]
+fn test_auto_renew_extends_expiry_until_cap() {
+    let env = Env::default();
+    let mut condition = create_test_condition(&env, SwapConditionType::PriceAbove(200000));
+    condition.auto_renew = true;
+    condition.renewal_period = 86400;
+    condition.max_renewals = 2;
+    let original_expiry = condition.expires_at;
+
+    // Outside the renewal window nothing happens
+    assert!(!condition.try_auto_renew(original_expiry - AUTO_RENEW_WINDOW - 1));
+    assert_eq!(condition.expires_at, original_expiry);
+
+    // Near expiry it extends by the renewal period
+    assert!(condition.try_auto_renew(original_expiry - 10));
+    assert_eq!(condition.expires_at, original_expiry + 86400);
+
+    assert!(condition.try_auto_renew(condition.expires_at - 10));
+    assert_eq!(condition.expires_at, original_expiry + 2 * 86400);
+    assert_eq!(condition.renewal_count, 2);
+
+    // Cap reached
+    assert!(!condition.try_auto_renew(condition.expires_at - 10));
+    assert_eq!(condition.expires_at, original_expiry + 2 * 86400);
+}
+
+#[test]
+fn test_cleanup_expired_conditions_detailed() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let mut short_request = create_test_swap_request(&env);
+    short_request.expires_at = env.ledger().timestamp() + MIN_CONDITION_LIFETIME;
+
+    let first_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), short_request.clone()).unwrap();
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    let third_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), short_request.clone()).unwrap();
+    let fourth_id = SmartSwap::create_swap_condition(env.clone(), user, short_request).unwrap();
+
+    env.ledger().with_mut(|li| {
+        li.timestamp += MIN_CONDITION_LIFETIME + 1;
+    });
+
+    // Limit caps how many are expired per call
+    let expired = SmartSwap::cleanup_expired_detailed(env.clone(), 2);
+    assert_eq!(expired.len(), 2);
+    assert_eq!(expired.get(0).unwrap(), first_id);
+    assert_eq!(expired.get(1).unwrap(), third_id);
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).active_conditions_count, 2);
+
+    let expired = SmartSwap::cleanup_expired_detailed(env.clone(), 10);
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired.get(0).unwrap(), fourth_id);
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).active_conditions_count, 1);
+}
+
+#[test]
+fn test_get_condition_liveness() {
+    let (env, admin, user, _oracle) = create_test_env();
+
+    // Active and triggered (mock USDC price is 1000000)
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let ready_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let liveness = SmartSwap::get_condition_liveness(env.clone(), ready_id).unwrap();
+    assert!(liveness.is_active);
+    assert!(!liveness.is_expired);
+    assert!(liveness.is_executable_now);
+    assert_eq!(liveness.next_eligible_time, Some(env.ledger().timestamp()));
+
+    // Active but trigger not met
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(2000000));
+    let idle_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let liveness = SmartSwap::get_condition_liveness(env.clone(), idle_id).unwrap();
+    assert!(liveness.is_active);
+    assert!(!liveness.is_executable_now);
+
+    // Execution paused
+    let flags = PauseFlags { create_paused: false, execute_paused: true, cancel_paused: false };
+    SmartSwap::set_pause_flags(env.clone(), admin, flags).unwrap();
+    assert!(!SmartSwap::get_condition_liveness(env.clone(), ready_id).unwrap().is_executable_now);
+
+    // Cancelled
+    SmartSwap::cancel_condition(env.clone(), user, idle_id).unwrap();
+    let liveness = SmartSwap::get_condition_liveness(env.clone(), idle_id).unwrap();
+    assert!(!liveness.is_active);
+    assert_eq!(liveness.next_eligible_time, None);
+
+    // Expired by time
+    env.ledger().with_mut(|li| {
+        li.timestamp += 86400 + 1;
+    });
+    let liveness = SmartSwap::get_condition_liveness(env.clone(), ready_id).unwrap();
+    assert!(liveness.is_expired);
+    assert!(!liveness.is_executable_now);
+}
+
+#[test]
+fn test_swap_deadline_configuration() {
+    let (env, admin, _user, _oracle) = create_test_env();
+
+    let result = SmartSwap::set_swap_deadline(env.clone(), admin.clone(), 10);
+    assert_eq!(result, Err(Symbol::new(&env, "invalid_swap_deadline")));
+    let result = SmartSwap::set_swap_deadline(env.clone(), admin.clone(), 7200);
+    assert_eq!(result, Err(Symbol::new(&env, "invalid_swap_deadline")));
+
+    SmartSwap::set_swap_deadline(env.clone(), admin, 60).unwrap();
+
+    // Deadline propagates into the params handed to the DEX
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let condition = create_test_condition(&env, SwapConditionType::PriceAbove(1));
+    let swap_params = SmartSwap::build_swap_params(&env, &config, &condition, condition.reference_price);
+    assert_eq!(swap_params.deadline, env.ledger().timestamp() + 60);
+}
+
+#[test]
+fn test_is_initialized() {
+    let env = Env::default();
+    assert!(!SmartSwap::is_initialized(env.clone()));
+
+    let admin = Address::generate(&env);
+    SmartSwap::initialize(env.clone(), admin.clone(), Address::generate(&env), Address::generate(&env)).unwrap();
+    assert!(SmartSwap::is_initialized(env.clone()));
+
+    // A rejected second initialize leaves the contract initialized
+    let result = SmartSwap::initialize(env.clone(), admin, Address::generate(&env), Address::generate(&env));
+    assert_eq!(result, Err(Symbol::new(&env, "already_initialized")));
+    assert!(SmartSwap::is_initialized(env.clone()));
+}
+
+#[test]
+fn test_create_before_initialize_rejected() {
+    let env = Env::default();
+    let user = Address::generate(&env);
+    let request = create_test_swap_request(&env);
+
+    let result = SmartSwap::create_swap_condition(env.clone(), user.clone(), request);
+    assert_eq!(result, Err(Symbol::new(&env, "not_initialized")));
+
+    // Nothing was written
+    assert!(!env.storage().instance().has(&DataKey::NextConditionId));
+    assert!(!env.storage().instance().has(&DataKey::SwapConditions));
+    assert!(!env.storage().instance().has(&DataKey::UserConditions(user)));
+    assert_eq!(SmartSwap::get_condition_owner(env.clone(), 1), None);
+}
+
+#[test]
+fn test_stale_quote_rejected() {
+    let env = Env::default();
+    let dex_config = DexConfigManager::create_default_config(&env, Address::generate(&env));
+
+    let quote = StellarDexIntegration::get_swap_quote(
+        &env,
+        &dex_config,
+        Symbol::new(&env, "USDC"),
+        Symbol::new(&env, "ETH"),
+        100_0000000,
+    )
+    .unwrap();
+    assert_eq!(quote.valid_until, env.ledger().timestamp() + dex_config.quote_validity_secs);
+
+    env.ledger().with_mut(|li| {
+        li.timestamp = quote.valid_until + 1;
+    });
+
+    let swap_params = SwapParams {
+        token_in: Symbol::new(&env, "USDC"),
+        token_out: Symbol::new(&env, "ETH"),
+        amount_in: 100_0000000,
+        amount_out_min: 1,
+        to: Address::generate(&env),
+        deadline: env.ledger().timestamp() + 300,
+        max_price_impact_bps: MAX_PRICE_IMPACT_BPS,
+    };
+
+    let result = StellarDexIntegration::execute_swap_with_quote(&env, &dex_config, swap_params, quote);
+    assert!(!result.success);
+    assert_eq!(result.error_message, Some(Symbol::new(&env, "quote_expired")));
+}
+
+#[test]
+fn test_oracle_dex_spread() {
+    let (env, _admin, _user, _oracle) = create_test_env();
+    let oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+
+    let oracle_output = PriceOracleClient::estimate_swap_output(
+        &env,
+        &oracle_config,
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "USDC"),
+        100_0000000,
+        DEFAULT_ASSET_DECIMALS,
+        DEFAULT_ASSET_DECIMALS,
+    )
+    .unwrap();
+    let quote = SmartSwap::get_swap_quote(
+        env.clone(),
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "USDC"),
+        100_0000000,
+    )
+    .unwrap();
+
+    let expected = ((quote.amount_out as i128 - oracle_output as i128) * 10000 / oracle_output as i128) as i32;
+    let spread = SmartSwap::get_oracle_dex_spread(
+        env.clone(),
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "USDC"),
+        100_0000000,
+    )
+    .unwrap();
+
+    assert_eq!(spread, expected);
+    // Simulated XLM/USDC pool prices XLM below the mock oracle
+    assert!(spread < 0);
+}
+
+#[test]
+fn test_price_impact_protection() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    // Large order against the thin default pool
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.amount_to_swap = 1_000_000_0000000;
+    let quote = SmartSwap::get_swap_quote(
+        env.clone(),
+        request.source_asset.clone(),
+        request.destination_asset.clone(),
+        request.amount_to_swap,
+    )
+    .unwrap();
+    assert!(quote.price_impact > 500);
+
+    request.max_price_impact_bps = quote.price_impact - 1;
+    let result = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone());
+    assert_eq!(result, Err(Symbol::new(&env, "price_impact_too_high")));
+
+    request.max_price_impact_bps = quote.price_impact;
+    assert!(SmartSwap::create_swap_condition(env.clone(), user, request.clone()).is_ok());
+
+    // Out of range bounds fail validation
+    request.max_price_impact_bps = 0;
+    assert_eq!(request.validate(&env).unwrap_err().error_code, 2010);
+    request.max_price_impact_bps = MAX_PRICE_IMPACT_BPS + 1;
+    assert_eq!(request.validate(&env).unwrap_err().error_code, 2010);
+
+    // The DEX rejects execution when the quote moves past the bound
+    let dex_config = DexConfigManager::create_default_config(&env, Address::generate(&env));
+    let swap_params = SwapParams {
+        token_in: request.source_asset.clone(),
+        token_out: request.destination_asset.clone(),
+        amount_in: request.amount_to_swap,
+        amount_out_min: 1,
+        to: Address::generate(&env),
+        deadline: env.ledger().timestamp() + 300,
+        max_price_impact_bps: quote.price_impact - 1,
+    };
+    let result = StellarDexIntegration::execute_swap_with_quote(&env, &dex_config, swap_params, quote);
+    assert!(!result.success);
+    assert_eq!(result.error_message, Some(Symbol::new(&env, "price_impact_too_high")));
+}
+
+#[test]
+fn test_retry_queue_on_transient_failure() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    // Oracle data below the confidence bar with no fallback
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let mut strict_config = config.oracle_config.clone();
+    strict_config.min_confidence = 90;
+    strict_config.fallback_enabled = false;
+    SmartSwap::update_oracle_config(env.clone(), admin.clone(), strict_config, true).unwrap();
+
+    // Repeated transient failures queue the condition once
+    for _ in 0..2 {
+        let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id);
+        assert_eq!(result, Ok(None));
+    }
+    let queue = SmartSwap::get_retry_queue(env.clone(), 10);
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue.get(0), Some(condition_id));
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 0).len(), 0);
+
+    // Recovered oracle lets the retry succeed and clears the entry
+    SmartSwap::update_oracle_config(env.clone(), admin, config.oracle_config, false).unwrap();
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.success);
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10).len(), 0);
+}
+
+#[test]
+fn test_oracle_failure_defers_instead_of_erroring() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    let mut strict_request = request;
+    strict_request.min_confidence_override = Some(90);
+    let strict_id = SmartSwap::create_swap_condition(env.clone(), user, strict_request).unwrap();
+
+    // A confidence override the price misses defers the check instead of failing the call
+    env.ledger().with_mut(|li| {
+        li.timestamp = 10;
+    });
+    assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), strict_id), Ok(None));
+
+    // So does a global oracle failure, reported as an error before the retry queue existed
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let mut strict_config = config.oracle_config.clone();
+    strict_config.min_confidence = 90;
+    strict_config.fallback_enabled = false;
+    SmartSwap::update_oracle_config(env.clone(), admin, strict_config, true).unwrap();
+    assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id), Ok(None));
+
+    // Neither counts as a check or a failure, the conditions just wait in the queue
+    for id in [strict_id, condition_id] {
+        let condition = SmartSwap::get_condition(env.clone(), id).unwrap();
+        assert_eq!(condition.status, SwapStatus::Active);
+        assert_eq!(condition.last_check, 0);
+        assert_eq!(condition.retry_count, 0);
+    }
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10), Vec::from_array(&env, [strict_id, condition_id]));
+}
+
+#[test]
+fn test_retry_queue_cleared_on_cancel_and_expiry() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let cancelled_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    let bulk_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    let expiring_id = SmartSwap::create_swap_condition(env.clone(), Address::generate(&env), request).unwrap();
+
+    // Queue all three behind an oracle outage
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let mut strict_config = config.oracle_config.clone();
+    strict_config.min_confidence = 90;
+    strict_config.fallback_enabled = false;
+    SmartSwap::update_oracle_config(env.clone(), admin, strict_config, true).unwrap();
+    for condition_id in [cancelled_id, bulk_id, expiring_id] {
+        assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id), Ok(None));
+    }
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10).len(), 3);
+
+    SmartSwap::cancel_condition(env.clone(), user.clone(), cancelled_id).unwrap();
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10), Vec::from_array(&env, [bulk_id, expiring_id]));
+
+    SmartSwap::cancel_all_conditions(env.clone(), user).unwrap();
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10), Vec::from_array(&env, [expiring_id]));
+
+    let expires_at = SmartSwap::get_condition(env.clone(), expiring_id).unwrap().expires_at;
+    env.ledger().with_mut(|li| {
+        li.timestamp = expires_at + 1;
+    });
+    assert_eq!(SmartSwap::cleanup_expired_conditions(env.clone(), 10), 1);
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10).len(), 0);
+}
+
+#[test]
+fn test_get_executable_conditions() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    // Mock USDC price is 1000000
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(900000));
+    let above_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(2000000));
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceBelow(1100000));
+    let below_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceBelow(500000));
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    // Triggerable but cancelled
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let cancelled_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    SmartSwap::cancel_condition(env.clone(), user, cancelled_id).unwrap();
+
+    let executable = SmartSwap::get_executable_conditions(env.clone(), 10);
+    assert_eq!(executable.len(), 2);
+    assert!(executable.contains(&above_id));
+    assert!(executable.contains(&below_id));
+
+    assert_eq!(SmartSwap::get_executable_conditions(env.clone(), 1).len(), 1);
+
+    // Nothing is executable once the conditions expire
+    env.ledger().with_mut(|li| {
+        li.timestamp += 86400 + 1;
+    });
+    assert_eq!(SmartSwap::get_executable_conditions(env.clone(), 10).len(), 0);
+}
+
+#[test]
+fn test_recurring_requires_interval() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    // A single execution needs no interval
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    assert!(request.validate(&env).is_ok());
+
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.max_executions = 0;
+    assert_eq!(request.validate(&env).unwrap_err().error_code, 2019);
+    let result = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone());
+    assert_eq!(result, Err(Symbol::new(&env, "recurring_requires_interval")));
+
+    request.max_executions = 3;
+    assert_eq!(request.validate(&env).unwrap_err().error_code, 2019);
+
+    request.execution_interval = 3600;
+    assert!(SmartSwap::create_swap_condition(env.clone(), user, request).is_ok());
+}
+
+#[test]
+fn test_activation_delay() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let now = env.ledger().timestamp();
+
+    // Delay pushing activation past expiry is rejected
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.expires_at = now + 3600;
+    request.activation_delay_secs = 3600;
+    assert_eq!(request.validate(&env).unwrap_err().error_code, 2011);
+    let result = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone());
+    assert_eq!(result, Err(Symbol::new(&env, "expires_before_activation")));
+
+    // Delay leaving less than the minimum active lifetime is rejected too
+    request.activation_delay_secs = 3600 - MIN_CONDITION_LIFETIME + 1;
+    assert_eq!(request.validate(&env).unwrap_err().error_code, 2011);
+
+    // Including the boundary where expiry lands exactly at the end of the minimum lifetime
+    request.activation_delay_secs = 3600 - MIN_CONDITION_LIFETIME;
+    assert_eq!(request.validate(&env).unwrap_err().error_code, 2011);
+
+    request.activation_delay_secs = 600;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+    let liveness = SmartSwap::get_condition_liveness(env.clone(), condition_id).unwrap();
+    assert!(!liveness.is_executable_now);
+    assert_eq!(liveness.next_eligible_time, Some(now + 600));
+
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id);
+    assert_eq!(result, Err(Symbol::new(&env, "not_yet_active")));
+
+    env.ledger().with_mut(|li| {
+        li.timestamp = now + 600;
+    });
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.success);
+}
+
+#[test]
+fn test_partial_execution_in_chunks() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.chunk_size = 40_0000000; // 40 USDC per fill
+    request.execution_interval = 60;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    // Fills of 40, 40 and the 20 remainder, spaced by the interval
+    let expected_fills = [40_0000000u64, 40_0000000, 20_0000000];
+    for (index, expected_fill) in expected_fills.iter().enumerate() {
+        if index > 0 {
+            let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id);
+            assert_eq!(result, Err(Symbol::new(&env, "execution_interval_not_elapsed")));
+
+            env.ledger().with_mut(|li| {
+                li.timestamp += 60;
+            });
+        }
+
+        let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(execution.amount_in, *expected_fill);
+
+        let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+        if index < expected_fills.len() - 1 {
+            assert_eq!(condition.status, SwapStatus::Active);
+            assert_eq!(condition.execution_count, 0);
+        }
+    }
+
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.amount_remaining, 0);
+    assert_eq!(condition.execution_count, 1);
+    assert_eq!(condition.status, SwapStatus::Executed);
+    assert_eq!(SmartSwap::get_condition_executions(env.clone(), condition_id).len(), 3);
+
+    // Chunk larger than the order is rejected
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.chunk_size = request.amount_to_swap + 1;
+    assert_eq!(request.validate(&env).unwrap_err().error_code, 2012);
+}
+
+#[test]
+fn test_get_spot_price() {
+    let (env, _admin, _user, _oracle) = create_test_env();
+    let xlm = Symbol::new(&env, "XLM");
+    let usdc = Symbol::new(&env, "USDC");
+
+    // Simulated pool holds 10M XLM against 1.2M USDC
+    let spot_price = SmartSwap::get_spot_price(env.clone(), xlm.clone(), usdc.clone()).unwrap();
+    assert_eq!(spot_price, 120000);
+
+    // Spot ignores fees and size, so it sits above what a real swap returns per unit
+    let quote = SmartSwap::get_swap_quote(env.clone(), xlm.clone(), usdc.clone(), 1_0000000).unwrap();
+    assert!(quote.amount_out < spot_price);
+
+    let oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+    let oracle_rate = PriceOracleClient::calculate_exchange_rate(
+        &env,
+        &oracle_config,
+        xlm,
+        usdc,
+        DEFAULT_ASSET_DECIMALS,
+        DEFAULT_ASSET_DECIMALS,
+    )
+    .unwrap();
+    assert_ne!(spot_price, oracle_rate);
+}
+
+#[test]
+fn test_update_condition() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(2000000));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let original = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+
+    let new_expires_at = env.ledger().timestamp() + 7200;
+    SmartSwap::update_condition(
+        env.clone(),
+        user.clone(),
+        condition_id,
+        100,
+        new_expires_at,
+        SwapConditionType::PriceAbove(1500000),
+    )
+    .unwrap();
+
+    let updated = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(updated.max_slippage, 100);
+    assert_eq!(updated.expires_at, new_expires_at);
+    assert_eq!(updated.condition_type, SwapConditionType::PriceAbove(1500000));
+    assert!(updated.min_amount_out > original.min_amount_out);
+    assert_eq!(updated.source_asset, original.source_asset);
+    assert_eq!(updated.reference_price, original.reference_price);
+
+    // Invalid slippage is rejected and leaves the condition unchanged
+    let result = SmartSwap::update_condition(
+        env.clone(),
+        user.clone(),
+        condition_id,
+        MAX_SLIPPAGE_BASIS_POINTS + 1,
+        new_expires_at,
+        SwapConditionType::PriceAbove(1500000),
+    );
+    assert_eq!(result, Err(Symbol::new(&env, "slippage_too_high")));
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap(), updated);
+
+    // Only the owner may update
+    let result = SmartSwap::update_condition(
+        env.clone(),
+        Address::generate(&env),
+        condition_id,
+        100,
+        new_expires_at,
+        SwapConditionType::PriceAbove(1500000),
+    );
+    assert_eq!(result, Err(Symbol::new(&env, "not_owner")));
+
+    // Cancelled conditions can't be updated
+    SmartSwap::cancel_condition(env.clone(), user.clone(), condition_id).unwrap();
+    let result = SmartSwap::update_condition(
+        env.clone(),
+        user,
+        condition_id,
+        100,
+        new_expires_at,
+        SwapConditionType::PriceAbove(1500000),
+    );
+    assert_eq!(result, Err(Symbol::new(&env, "cannot_update")));
+}
+
+#[test]
+fn test_prune_executions() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.max_executions = 5;
+    request.execution_interval = 1;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    for _ in 0..5 {
+        env.ledger().with_mut(|li| {
+            li.timestamp += 1;
+        });
+        SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id)
+            .unwrap()
+            .unwrap();
+    }
+    let history = SmartSwap::get_condition_executions(env.clone(), condition_id);
+    assert_eq!(history.len(), 5);
+
+    // Only the owner may prune
+    let result = SmartSwap::prune_executions(env.clone(), Address::generate(&env), condition_id, 2);
+    assert_eq!(result, Err(Symbol::new(&env, "not_owner")));
+
+    let removed = SmartSwap::prune_executions(env.clone(), user.clone(), condition_id, 2).unwrap();
+    assert_eq!(removed, 3);
+
+    // The most recent records are kept in order
+    let pruned = SmartSwap::get_condition_executions(env.clone(), condition_id);
+    assert_eq!(pruned.len(), 2);
+    assert_eq!(pruned.get(0), history.get(3));
+    assert_eq!(pruned.get(1), history.get(4));
+
+    // Nothing left to remove within the window
+    assert_eq!(SmartSwap::prune_executions(env.clone(), user, condition_id, 2).unwrap(), 0);
+}
+
+#[test]
+fn test_per_asset_volume() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let usdc_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.source_asset = Symbol::new(&env, "ETH");
+    request.destination_asset = Symbol::new(&env, "USDC");
+    request.amount_to_swap = 50_0000000;
+    let eth_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), usdc_id).unwrap().unwrap();
+    assert_eq!(SmartSwap::get_asset_volume(env.clone(), Symbol::new(&env, "USDC")), 100_0000000);
+    assert_eq!(SmartSwap::get_asset_volume(env.clone(), Symbol::new(&env, "ETH")), 0);
+
+    SmartSwap::check_and_execute_condition(env.clone(), keeper, eth_id).unwrap().unwrap();
+    assert_eq!(SmartSwap::get_asset_volume(env.clone(), Symbol::new(&env, "USDC")), 100_0000000);
+    assert_eq!(SmartSwap::get_asset_volume(env.clone(), Symbol::new(&env, "ETH")), 50_0000000);
+
+    // The aggregate stays a raw sum
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).total_volume_swapped, 150_0000000);
+}
+
+fn set_reference_price(env: &Env, condition_id: u64, reference_price: u64) {
+    let mut conditions: Map<u64, SwapCondition> = env.storage().instance().get(&DataKey::SwapConditions).unwrap();
+    let mut condition = conditions.get(condition_id).unwrap();
+    condition.reference_price = reference_price;
+    conditions.set(condition_id, condition);
+    env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+}
+
+#[test]
+fn test_price_deviation_circuit_breaker() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    // Mock USDC price is 1000000, a reference of a third puts it 200% above
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let above_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    set_reference_price(&env, above_id, 333333);
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), above_id);
+    assert_eq!(result, Err(Symbol::new(&env, "price_deviation_halt")));
+
+    // A triple reference puts the price 66% below, past a tightened 20% bound
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceBelow(2000000));
+    let below_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    set_reference_price(&env, below_id, 3000000);
+    assert_eq!(
+        SmartSwap::set_max_deviation(env.clone(), admin.clone(), 0),
+        Err(Symbol::new(&env, "invalid_max_deviation"))
+    );
+    SmartSwap::set_max_deviation(env.clone(), admin.clone(), 2000).unwrap();
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), below_id);
+    assert_eq!(result, Err(Symbol::new(&env, "price_deviation_halt")));
+    assert_eq!(SmartSwap::get_condition(env.clone(), below_id).unwrap().status, SwapStatus::Active);
+
+    // Loosening the bound lets execution through to the DEX, where the minimum output priced
+    // at the tripled price can't be filled and the condition is queued for retry
+    SmartSwap::set_max_deviation(env.clone(), admin, 50000).unwrap();
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper, above_id);
+    assert_eq!(result, Ok(None));
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10), Vec::from_array(&env, [above_id]));
+}
+
+#[test]
+fn test_trigger_beyond_deviation_bound_rejected() {
+    let (env, admin, user, _oracle) = create_test_env();
+
+    // A 200% increase is a valid trigger on its own, but three times the reference is past the
+    // default 100% circuit breaker bound and could never execute
+    let request = create_executable_swap_request(&env, SwapConditionType::PercentageIncrease(200));
+    assert!(request.validate(&env).is_ok());
+    let result = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone());
+    assert_eq!(result, Err(Symbol::new(&env, "trigger_beyond_deviation_bound")));
+
+    // Exactly at the bound is fine
+    let mut at_bound = request.clone();
+    at_bound.condition_type = SwapConditionType::PercentageIncrease(100);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), at_bound).unwrap();
+
+    // Updates are held to the same bound
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    let result = SmartSwap::update_condition(
+        env.clone(),
+        user.clone(),
+        condition_id,
+        condition.max_slippage,
+        condition.expires_at,
+        SwapConditionType::PriceAbove(2000001),
+    );
+    assert_eq!(result, Err(Symbol::new(&env, "trigger_beyond_deviation_bound")));
+
+    // Widening the breaker admits the trigger
+    SmartSwap::set_max_deviation(env.clone(), admin, 20000).unwrap();
+    assert!(SmartSwap::create_swap_condition(env.clone(), user, request).is_ok());
+}
+
+#[test]
+fn test_detect_conflicts() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    // Sell XLM when it rises
+    let mut sell = create_test_swap_request(&env);
+    sell.condition_type = SwapConditionType::PriceAbove(150000);
+    let sell_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), sell).unwrap();
+
+    // Unrelated pair
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(2000000));
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    assert_eq!(SmartSwap::detect_conflicts(env.clone(), user.clone()).len(), 0);
+
+    // Buy XLM back with USDC on the same pair
+    let mut buy = create_test_swap_request(&env);
+    buy.source_asset = Symbol::new(&env, "USDC");
+    buy.destination_asset = Symbol::new(&env, "XLM");
+    buy.condition_type = SwapConditionType::PriceBelow(1100000);
+    let buy_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), buy).unwrap();
+
+    let conflicts = SmartSwap::detect_conflicts(env.clone(), user.clone());
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts.get(0), Some((sell_id, buy_id)));
+
+    // Cancelled conditions no longer conflict
+    SmartSwap::cancel_condition(env.clone(), user.clone(), buy_id).unwrap();
+    assert_eq!(SmartSwap::detect_conflicts(env.clone(), user).len(), 0);
+}
+
+#[test]
+fn test_deadline_capped_at_quote_validity() {
+    let (env, _admin, _user, _oracle) = create_test_env();
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+
+    let condition = create_test_condition(&env, SwapConditionType::PriceAbove(1));
+    let quote = StellarDexIntegration::get_swap_quote(
+        &env,
+        &config.dex_config,
+        condition.source_asset.clone(),
+        condition.destination_asset.clone(),
+        condition.amount_to_swap,
+    )
+    .unwrap();
+
+    // Default swap deadline outlives the quote, so the quote bounds it
+    let swap_params = SmartSwap::build_swap_params(&env, &config, &condition, condition.reference_price);
+    assert!(swap_params.deadline > quote.valid_until);
+    assert_eq!(StellarDexIntegration::effective_deadline(&swap_params, &quote), quote.valid_until);
+
+    // A deadline shorter than the quote is left alone
+    let mut short_params = swap_params.clone();
+    short_params.deadline = quote.valid_until - 1;
+    assert_eq!(StellarDexIntegration::effective_deadline(&short_params, &quote), quote.valid_until - 1);
+}
+
+#[test]
+fn test_get_multiple_prices_lenient() {
+    let env = Env::default();
+    let oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+
+    let assets = [
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "DOGE"),
+        Symbol::new(&env, "USDC"),
+    ];
+
+    // The strict variant fails the whole batch
+    assert!(PriceOracleClient::get_multiple_prices(&env, &oracle_config, &assets).is_err());
+
+    let results = PriceOracleClient::get_multiple_prices_lenient(&env, &oracle_config, &assets);
+    assert_eq!(results.len(), 3);
+
+    let xlm = results.get(0).unwrap();
+    assert!(xlm.success);
+    assert_eq!(xlm.price_data.unwrap().asset_symbol, Symbol::new(&env, "XLM"));
+
+    let unknown = results.get(1).unwrap();
+    assert!(!unknown.success);
+    assert!(unknown.price_data.is_none());
+    assert!(unknown.error_message.is_some());
+
+    let usdc = results.get(2).unwrap();
+    assert!(usdc.success);
+    assert_eq!(usdc.price_data.unwrap().price, 1000000);
+}
+
+#[test]
+fn test_exchange_rate_conditions() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    // Mock USDC/ETH rate is 1000000 * 1e7 / 3000000000 = 3333
+    let oracle_config = OracleConfigManager::create_default_config(&env, Address::generate(&env));
+    let rate = PriceOracleClient::calculate_exchange_rate(
+        &env,
+        &oracle_config,
+        Symbol::new(&env, "USDC"),
+        Symbol::new(&env, "ETH"),
+        DEFAULT_ASSET_DECIMALS,
+        DEFAULT_ASSET_DECIMALS,
+    )
+    .unwrap();
+    assert_eq!(rate, 3333);
+
+    // Single-price thresholds at the same level would behave differently, the USDC price is 1000000
+    let request = create_executable_swap_request(&env, SwapConditionType::RateAbove(4000));
+    let idle_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), idle_id).unwrap();
+    assert!(result.is_none());
+
+    let request = create_executable_swap_request(&env, SwapConditionType::RateAbove(3000));
+    let above_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let request = create_executable_swap_request(&env, SwapConditionType::RateBelow(4000));
+    let below_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    let executable = SmartSwap::get_executable_conditions(env.clone(), 10);
+    assert_eq!(executable.len(), 2);
+    assert!(executable.contains(&above_id));
+    assert!(executable.contains(&below_id));
+
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, above_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.success);
+
+    // A zero rate fails validation
+    let request = create_executable_swap_request(&env, SwapConditionType::RateBelow(0));
+    assert_eq!(request.validate(&env).unwrap_err().error_code, 2110);
+}
+
+#[test]
+fn test_set_condition_memo() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let request = create_test_swap_request(&env);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().memo, None);
+
+    SmartSwap::set_condition_memo(env.clone(), user.clone(), condition_id, Symbol::new(&env, "rent_money")).unwrap();
+    assert_eq!(
+        SmartSwap::get_condition(env.clone(), condition_id).unwrap().memo,
+        Some(Symbol::new(&env, "rent_money"))
+    );
+
+    SmartSwap::set_condition_memo(env.clone(), user.clone(), condition_id, Symbol::new(&env, "take_profit")).unwrap();
+    assert_eq!(
+        SmartSwap::get_condition(env.clone(), condition_id).unwrap().memo,
+        Some(Symbol::new(&env, "take_profit"))
+    );
+
+    let result = SmartSwap::set_condition_memo(env.clone(), Address::generate(&env), condition_id, Symbol::new(&env, "x"));
+    assert_eq!(result, Err(Symbol::new(&env, "not_owner")));
+
+    // Empty memo clears it
+    SmartSwap::set_condition_memo(env.clone(), user, condition_id, Symbol::new(&env, "")).unwrap();
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().memo, None);
+}
+
+#[test]
+fn test_fee_revenue_in_range() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    SmartSwap::set_protocol_fee(env.clone(), admin.clone(), 50).unwrap(); // 0.5%
+    assert_eq!(
+        SmartSwap::set_protocol_fee(env.clone(), admin.clone(), MAX_PROTOCOL_FEE_BPS + 1),
+        Err(Symbol::new(&env, "invalid_protocol_fee"))
+    );
+
+    // Three 100 USDC executions at t=1000, t=2000 and t=3000, each paying 0.5 USDC
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.max_executions = 3;
+    request.execution_interval = 1000;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+    for timestamp in [1000u64, 2000, 3000] {
+        env.ledger().with_mut(|li| {
+            li.timestamp = timestamp;
+        });
+        let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(execution.protocol_fee, 5000000);
+    }
+
+    let all = SmartSwap::get_fee_revenue_in_range(env.clone(), admin.clone(), 0, 3000).unwrap();
+    assert_eq!(all, 15000000);
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).total_fees_collected, all);
+
+    let subset = SmartSwap::get_fee_revenue_in_range(env.clone(), admin.clone(), 1500, 3000).unwrap();
+    assert_eq!(subset, 10000000);
+
+    assert_eq!(
+        SmartSwap::get_fee_revenue_in_range(env.clone(), admin.clone(), 3000, 1000),
+        Err(Symbol::new(&env, "invalid_time_range"))
+    );
+    assert!(SmartSwap::get_fee_revenue_in_range(env.clone(), Address::generate(&env), 0, 3000).is_err());
+}
+
+#[test]
+fn test_reentrant_execution_rejected() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    // State a DEX callback would observe mid-swap
+    let mut conditions: Map<u64, SwapCondition> = env.storage().instance().get(&DataKey::SwapConditions).unwrap();
+    let mut condition = conditions.get(condition_id).unwrap();
+    condition.status = SwapStatus::Executing;
+    conditions.set(condition_id, condition.clone());
+    env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id);
+    assert_eq!(result, Err(Symbol::new(&env, "reentrant_execution")));
+    assert_eq!(SmartSwap::get_condition_executions(env.clone(), condition_id).len(), 0);
+    assert_eq!(SmartSwap::get_status_counts(env.clone()).active, 1);
+
+    // Once the in-flight swap settles the marker is cleared and execution completes once
+    condition.status = SwapStatus::Active;
+    conditions.set(condition_id, condition);
+    env.storage().instance().set(&DataKey::SwapConditions, &conditions);
+
+    SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id)
+        .unwrap()
+        .unwrap();
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.status, SwapStatus::Executed);
+    assert_eq!(SmartSwap::get_condition_executions(env.clone(), condition_id).len(), 1);
+}
+
+#[test]
+fn test_create_funded_condition() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SmartSwap, ());
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let usdc_admin = token::StellarAssetClient::new(&env, &usdc.address());
+    let usdc_client = token::Client::new(&env, &usdc.address());
+    usdc_admin.mint(&user, &50_0000000);
+
+    env.as_contract(&contract_id, || {
+        SmartSwap::initialize(env.clone(), admin.clone(), Address::generate(&env), Address::generate(&env)).unwrap();
+
+        // No token registered for the asset yet
+        let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+        let result = SmartSwap::create_funded_condition(env.clone(), user.clone(), request.clone());
+        assert_eq!(result, Err(Symbol::new(&env, "asset_token_not_set")));
+
+        SmartSwap::set_asset_token(env.clone(), admin.clone(), Symbol::new(&env, "USDC"), usdc.address()).unwrap();
+
+        // 100 USDC requested against a 50 USDC balance
+        let result = SmartSwap::create_funded_condition(env.clone(), user.clone(), request.clone());
+        assert_eq!(result, Err(Symbol::new(&env, "insufficient_balance")));
+        assert!(!env.storage().instance().has(&DataKey::NextConditionId));
+        assert_eq!(SmartSwap::get_user_conditions(env.clone(), user.clone()).len(), 0);
+        assert_eq!(usdc_client.balance(&contract_id), 0);
+
+        // Funded request escrows the full amount
+        let mut request = request;
+        request.amount_to_swap = 40_0000000;
+        let condition_id = SmartSwap::create_funded_condition(env.clone(), user.clone(), request).unwrap();
+        assert_eq!(SmartSwap::get_condition_escrow(env.clone(), condition_id), 40_0000000);
+        assert_eq!(usdc_client.balance(&user), 10_0000000);
+        assert_eq!(usdc_client.balance(&contract_id), 40_0000000);
+
+        // Cancelling refunds it
+        SmartSwap::cancel_condition(env.clone(), user.clone(), condition_id).unwrap();
+        assert_eq!(SmartSwap::get_condition_escrow(env.clone(), condition_id), 0);
+        assert_eq!(usdc_client.balance(&user), 50_0000000);
+    });
+}
+
+#[test]
+fn test_funded_condition_escrow_refunded_on_expiry() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SmartSwap, ());
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let keeper = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let usdc_client = token::Client::new(&env, &usdc.address());
+    token::StellarAssetClient::new(&env, &usdc.address()).mint(&user, &40_0000000);
+
+    env.as_contract(&contract_id, || {
+        SmartSwap::initialize(env.clone(), admin.clone(), Address::generate(&env), Address::generate(&env)).unwrap();
+        SmartSwap::set_asset_token(env.clone(), admin.clone(), Symbol::new(&env, "USDC"), usdc.address()).unwrap();
+
+        let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+        request.amount_to_swap = 40_0000000;
+        request.chunk_size = 10_0000000;
+        let condition_id = SmartSwap::create_funded_condition(env.clone(), user.clone(), request).unwrap();
+
+        // The simulated swap leaves the escrow whole
+        let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id).unwrap().unwrap();
+        assert!(execution.success);
+        assert_eq!(SmartSwap::get_condition_escrow(env.clone(), condition_id), 40_0000000);
+
+        // Expiry returns it to the owner
+        let expires_at = SmartSwap::get_condition(env.clone(), condition_id).unwrap().expires_at;
+        env.ledger().with_mut(|li| {
+            li.timestamp = expires_at + 1;
+        });
+        assert_eq!(SmartSwap::cleanup_expired_detailed(env.clone(), 10), Vec::from_array(&env, [condition_id]));
+        assert_eq!(SmartSwap::get_condition_escrow(env.clone(), condition_id), 0);
+        assert_eq!(usdc_client.balance(&user), 40_0000000);
+        assert_eq!(usdc_client.balance(&contract_id), 0);
+    });
+}
+
+#[test]
+fn test_funded_condition_escrow_refunded_on_execution() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SmartSwap, ());
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let keeper = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let usdc_client = token::Client::new(&env, &usdc.address());
+    token::StellarAssetClient::new(&env, &usdc.address()).mint(&user, &40_0000000);
+
+    env.as_contract(&contract_id, || {
+        SmartSwap::initialize(env.clone(), admin.clone(), Address::generate(&env), Address::generate(&env)).unwrap();
+        SmartSwap::set_asset_token(env.clone(), admin.clone(), Symbol::new(&env, "USDC"), usdc.address()).unwrap();
+
+        let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+        request.amount_to_swap = 40_0000000;
+        request.chunk_size = 20_0000000;
+        let condition_id = SmartSwap::create_funded_condition(env.clone(), user.clone(), request).unwrap();
+
+        // The first chunk leaves the condition open and the escrow held
+        SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id).unwrap().unwrap();
+        assert_eq!(SmartSwap::get_condition_escrow(env.clone(), condition_id), 40_0000000);
+        assert_eq!(usdc_client.balance(&contract_id), 40_0000000);
+
+        // The last chunk completes the condition and releases everything to the owner
+        env.ledger().with_mut(|li| {
+            li.timestamp += 1;
+        });
+        SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id).unwrap().unwrap();
+        assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().status, SwapStatus::Executed);
+        assert_eq!(SmartSwap::get_condition_escrow(env.clone(), condition_id), 0);
+        assert_eq!(usdc_client.balance(&user), 40_0000000);
+        assert_eq!(usdc_client.balance(&contract_id), 0);
+    });
+}
+
+#[test]
+fn test_funded_condition_escrow_refunded_on_failure() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SmartSwap, ());
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let keeper = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let usdc_client = token::Client::new(&env, &usdc.address());
+    token::StellarAssetClient::new(&env, &usdc.address()).mint(&user, &40_0000000);
+
+    env.as_contract(&contract_id, || {
+        SmartSwap::initialize(env.clone(), admin.clone(), Address::generate(&env), Address::generate(&env)).unwrap();
+        SmartSwap::set_asset_token(env.clone(), admin.clone(), Symbol::new(&env, "USDC"), usdc.address()).unwrap();
+
+        let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+        request.amount_to_swap = 40_0000000;
+        request.max_retries = 0;
+        let condition_id = SmartSwap::create_funded_condition(env.clone(), user.clone(), request).unwrap();
+        assert_eq!(usdc_client.balance(&user), 0);
+
+        // Liquidity dries up and the only attempt fails the condition
+        let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+        let mut shallow_config = config.dex_config;
+        shallow_config.min_liquidity = u64::MAX;
+        SmartSwap::update_dex_config(env.clone(), admin.clone(), shallow_config).unwrap();
+
+        assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id), Ok(None));
+        assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().status, SwapStatus::Failed);
+        assert_eq!(SmartSwap::get_condition_escrow(env.clone(), condition_id), 0);
+        assert_eq!(usdc_client.balance(&user), 40_0000000);
+        assert_eq!(usdc_client.balance(&contract_id), 0);
+    });
+}
+
+#[test]
+fn test_funded_condition_cannot_be_transferred() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SmartSwap, ());
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    let usdc = env.register_stellar_asset_contract_v2(admin.clone());
+    let usdc_client = token::Client::new(&env, &usdc.address());
+    token::StellarAssetClient::new(&env, &usdc.address()).mint(&user, &40_0000000);
+
+    env.as_contract(&contract_id, || {
+        SmartSwap::initialize(env.clone(), admin.clone(), Address::generate(&env), Address::generate(&env)).unwrap();
+        SmartSwap::set_asset_token(env.clone(), admin.clone(), Symbol::new(&env, "USDC"), usdc.address()).unwrap();
+
+        let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+        request.amount_to_swap = 40_0000000;
+        request.chunk_size = 10_0000000;
+        let condition_id = SmartSwap::create_funded_condition(env.clone(), user.clone(), request).unwrap();
+
+        assert_eq!(
+            SmartSwap::transfer_condition(env.clone(), user.clone(), condition_id, recipient.clone()),
+            Err(Symbol::new(&env, "condition_funded"))
+        );
+        assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().owner, user);
+
+        // Cancelling still refunds the funder
+        SmartSwap::cancel_condition(env.clone(), user.clone(), condition_id).unwrap();
+        assert_eq!(usdc_client.balance(&user), 40_0000000);
+        assert_eq!(usdc_client.balance(&recipient), 0);
+    });
+}
+
+#[test]
+fn test_get_recent_executions_paging() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    assert_eq!(SmartSwap::get_recent_executions(env.clone(), 0, 10).len(), 0);
+
+    // Alternate executions between two recurring conditions
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.max_executions = 3;
+    request.execution_interval = 100;
+    let first_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    let second_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    for round in 0..3u64 {
+        env.ledger().with_mut(|li| {
+            li.timestamp = 100 * (round + 1);
+        });
+        SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), first_id).unwrap().unwrap();
+        SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), second_id).unwrap().unwrap();
+    }
+
+    // Newest first across both conditions
+    let page = SmartSwap::get_recent_executions(env.clone(), 0, 4);
+    assert_eq!(page.len(), 4);
+    assert_eq!(page.get(0).unwrap().condition_id, second_id);
+    assert_eq!(page.get(0).unwrap().executed_at, 300);
+    assert_eq!(page.get(1).unwrap().condition_id, first_id);
+    assert_eq!(page.get(3).unwrap().executed_at, 200);
+
+    // The next page holds the remainder, then nothing
+    let page = SmartSwap::get_recent_executions(env.clone(), 4, 4);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(1).unwrap().condition_id, first_id);
+    assert_eq!(page.get(1).unwrap().executed_at, 100);
+    assert_eq!(SmartSwap::get_recent_executions(env.clone(), 6, 4).len(), 0);
+
+    // Oversized pages are capped
+    assert_eq!(SmartSwap::get_recent_executions(env.clone(), 0, MAX_EXECUTION_PAGE_SIZE + 10).len(), 6);
+}
+
+#[test]
+fn test_keeper_rewards_accrue_and_claim() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SmartSwap, ());
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let payout = Address::generate(&env);
+
+    let reward_token = env.register_stellar_asset_contract_v2(admin.clone());
+    token::StellarAssetClient::new(&env, &reward_token.address()).mint(&admin, &1_0000000);
+    let reward_client = token::Client::new(&env, &reward_token.address());
+
+    env.as_contract(&contract_id, || {
+        SmartSwap::initialize(env.clone(), admin.clone(), Address::generate(&env), Address::generate(&env)).unwrap();
+
+        let result = SmartSwap::claim_keeper_rewards(env.clone(), keeper.clone(), payout.clone());
+        assert_eq!(result, Err(Symbol::new(&env, "reward_token_not_set")));
+        SmartSwap::set_reward_token(env.clone(), admin.clone(), Symbol::new(&env, "USDC"), reward_token.address()).unwrap();
+
+        let result = SmartSwap::claim_keeper_rewards(env.clone(), keeper.clone(), payout.clone());
+        assert_eq!(result, Err(Symbol::new(&env, "no_rewards")));
+
+        // Two 100 USDC executions at the default 0.1% reward
+        for _ in 0..2 {
+            let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+            let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+            SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id)
+                .unwrap()
+                .unwrap();
+        }
+        assert_eq!(SmartSwap::get_keeper_rewards(env.clone(), keeper.clone()), 2000000);
+        assert_eq!(SmartSwap::get_keeper_rewards(env.clone(), user.clone()), 0);
+
+        // Claims are paid from the funded pool only
+        let result = SmartSwap::claim_keeper_rewards(env.clone(), keeper.clone(), payout.clone());
+        assert_eq!(result, Err(Symbol::new(&env, "insufficient_reward_pool")));
+        assert_eq!(SmartSwap::fund_keeper_rewards(env.clone(), admin.clone(), 0), Err(Symbol::new(&env, "zero_amount")));
+        SmartSwap::fund_keeper_rewards(env.clone(), admin.clone(), 1_0000000).unwrap();
+        assert_eq!(SmartSwap::get_keeper_reward_pool(env.clone()), 1_0000000);
+        assert_eq!(reward_client.balance(&contract_id), 1_0000000);
+
+        let claimed = SmartSwap::claim_keeper_rewards(env.clone(), keeper.clone(), payout.clone()).unwrap();
+        assert_eq!(claimed, 2000000);
+        assert_eq!(SmartSwap::get_keeper_rewards(env.clone(), keeper.clone()), 0);
+        assert_eq!(SmartSwap::get_keeper_reward_pool(env.clone()), 8000000);
+        assert_eq!(reward_client.balance(&payout), 2000000);
+
+        // The payout unit can't change under a funded pool
+        let result = SmartSwap::set_reward_token(env.clone(), admin.clone(), Symbol::new(&env, "ETH"), Address::generate(&env));
+        assert_eq!(result, Err(Symbol::new(&env, "reward_pool_not_empty")));
+    });
+}
+
+#[test]
+fn test_keeper_rewards_accrue_in_reward_token_units() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    // Without a reward token there is no unit to accrue in
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id).unwrap().unwrap();
+    assert_eq!(execution.keeper_reward, 1000000);
+    assert_eq!(SmartSwap::get_keeper_rewards(env.clone(), keeper.clone()), 0);
+
+    // 0.1 USDC of reward at 1000000 per USDC is worth 333 units of ETH at 3000000000
+    SmartSwap::set_reward_token(env.clone(), admin, Symbol::new(&env, "ETH"), Address::generate(&env)).unwrap();
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id).unwrap().unwrap();
+    assert_eq!(execution.keeper_reward, 1000000);
+    assert_eq!(SmartSwap::get_keeper_rewards(env.clone(), keeper), 333);
+}
+
+#[test]
+fn test_native_and_issued_asset_routing() {
+    let env = Env::default();
+    let dex_config = DexConfigManager::create_default_config(&env, Address::generate(&env));
+
+    // Native -> issued
+    let native_quote = StellarDexIntegration::get_swap_quote(
+        &env,
+        &dex_config,
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "USDC"),
+        100_0000000,
+    )
+    .unwrap();
+    assert_eq!(native_quote.route.asset_in, AssetType::Native);
+    assert!(matches!(native_quote.route.asset_out, AssetType::Issued(_)));
+
+    // Issued -> issued
+    let issued_quote = StellarDexIntegration::get_swap_quote(
+        &env,
+        &dex_config,
+        Symbol::new(&env, "USDC"),
+        Symbol::new(&env, "ETH"),
+        100_0000000,
+    )
+    .unwrap();
+    assert!(matches!(issued_quote.route.asset_in, AssetType::Issued(_)));
+    assert!(matches!(issued_quote.route.asset_out, AssetType::Issued(_)));
+
+    // Skipping the token contract call makes native legs cheaper
+    assert!(native_quote.estimated_gas < issued_quote.estimated_gas);
+
+    let pool_info = StellarDexIntegration::get_pool_info(
+        &env,
+        &dex_config,
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "USDC"),
+    )
+    .unwrap();
+    assert_eq!(pool_info.asset_a, AssetType::Native);
+    assert!(matches!(pool_info.asset_b, AssetType::Issued(_)));
+}
+
+
+#[test]
+fn test_unregistered_asset_routed_by_symbol() {
+    let env = Env::default();
+    let dex_config = DexConfigManager::create_default_config(&env, Address::generate(&env));
+    let xlm = Symbol::new(&env, "XLM");
+    let usdc = Symbol::new(&env, "USDC");
+
+    // Without a registered token the issued asset still routes, just without an address
+    assert_eq!(StellarDexIntegration::resolve_asset_type(&env, &xlm), AssetType::Native);
+    assert_eq!(StellarDexIntegration::resolve_asset_type(&env, &usdc), AssetType::Issued(None));
+    let quote = StellarDexIntegration::get_swap_quote(&env, &dex_config, xlm.clone(), usdc.clone(), 100_0000000).unwrap();
+    assert_eq!(quote.route.asset_out, AssetType::Issued(None));
+
+    // Once registered, the route carries the token contract
+    let usdc_token = Address::generate(&env);
+    env.storage().instance().set(&DataKey::AssetToken(usdc.clone()), &usdc_token);
+    assert_eq!(StellarDexIntegration::resolve_asset_type(&env, &usdc), AssetType::Issued(Some(usdc_token.clone())));
+    let quote = StellarDexIntegration::get_swap_quote(&env, &dex_config, xlm, usdc, 100_0000000).unwrap();
+    assert_eq!(quote.route.asset_out, AssetType::Issued(Some(usdc_token)));
+}
+
+#[test]
+fn test_max_pending_volume_per_asset() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let usdc = Symbol::new(&env, "USDC");
+
+    SmartSwap::set_max_pending_per_asset(env.clone(), admin, 300_0000000).unwrap();
+
+    // Three 100 USDC conditions fill the cap
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(2000000));
+    let first_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    let request_executable = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let executable_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request_executable).unwrap();
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), usdc.clone()), 300_0000000);
+
+    let result = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone());
+    assert_eq!(result, Err(Symbol::new(&env, "volume_cap_exceeded")));
+
+    // Other source assets are tracked separately
+    let mut eth_request = request.clone();
+    eth_request.source_asset = Symbol::new(&env, "ETH");
+    eth_request.destination_asset = usdc.clone();
+    assert!(SmartSwap::create_swap_condition(env.clone(), user.clone(), eth_request).is_ok());
+
+    // Cancellation frees capacity
+    SmartSwap::cancel_condition(env.clone(), user.clone(), first_id).unwrap();
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), usdc.clone()), 200_0000000);
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    // So does execution
+    SmartSwap::check_and_execute_condition(env.clone(), keeper, executable_id).unwrap().unwrap();
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), usdc.clone()), 200_0000000);
+
+    // And expiry
+    env.ledger().with_mut(|li| {
+        li.timestamp += 86400 + 1;
+    });
+    SmartSwap::cleanup_expired_conditions(env.clone(), 10);
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), usdc), 0);
+}
+
+#[test]
+fn test_target_price_tolerance_too_tight() {
+    let env = Env::default();
+
+    // 0.1% of 500 rounds down to a zero-width band
+    let mut request = create_test_swap_request(&env);
+    request.condition_type = SwapConditionType::TargetPrice(500, DEFAULT_TARGET_TOLERANCE_BPS);
+    let error = request.validate(&env).unwrap_err();
+    assert_eq!(error.error_code, 2111);
+    assert_eq!(error.message, Symbol::new(&env, "tolerance_too_tight"));
+
+    // Smallest target whose band spans one unit
+    request.condition_type = SwapConditionType::TargetPrice(1000, DEFAULT_TARGET_TOLERANCE_BPS);
+    assert!(request.validate(&env).is_ok());
+}
+
+#[test]
+fn test_simulate_execution_matches_execution() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    // Trigger not met, simulation still projects the swap
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(2000000));
+    let idle_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let simulated = SmartSwap::simulate_execution(env.clone(), idle_id).unwrap();
+    assert!(simulated.amount_out > 0);
+    assert!(SmartSwap::get_condition_executions(env.clone(), idle_id).is_empty());
+
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+    let simulated = SmartSwap::simulate_execution(env.clone(), condition_id).unwrap();
+    let condition_before = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition_before.execution_count, 0);
+
+    let executed = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(simulated.amount_in, executed.amount_in);
+    assert_eq!(simulated.amount_out, executed.amount_out);
+    assert_eq!(simulated.actual_slippage, executed.actual_slippage);
+    assert_eq!(simulated.price_impact, executed.price_impact);
+    assert_eq!(simulated.gas_used, executed.gas_used);
+    assert_eq!(simulated.execution_price, executed.execution_price);
+    assert_eq!(simulated.keeper_reward, executed.keeper_reward);
+    assert_eq!(simulated.protocol_fee, executed.protocol_fee);
+}
+
+#[test]
+fn test_admin_refresh_references() {
+    let (env, admin, user, _oracle) = create_test_env();
+
+    let request = create_executable_swap_request(&env, SwapConditionType::PercentageIncrease(10));
+    let increase_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let request = create_executable_swap_request(&env, SwapConditionType::PercentageDecrease(10));
+    let decrease_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(2000000));
+    let threshold_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let request = create_executable_swap_request(&env, SwapConditionType::PercentageIncrease(10));
+    let cancelled_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    SmartSwap::cancel_condition(env.clone(), user, cancelled_id).unwrap();
+
+    // Stale references left over from an outage
+    for condition_id in [increase_id, decrease_id, threshold_id, cancelled_id] {
+        set_reference_price(&env, condition_id, 500000);
+    }
+
+    let mut ids = Vec::new(&env);
+    for condition_id in [increase_id, decrease_id, threshold_id, cancelled_id, 999] {
+        ids.push_back(condition_id);
+    }
+
+    assert!(SmartSwap::admin_refresh_references(env.clone(), Address::generate(&env), ids.clone()).is_err());
+
+    let updated = SmartSwap::admin_refresh_references(env.clone(), admin, ids).unwrap();
+    assert_eq!(updated, 2);
+
+    // Mock USDC price is 1000000
+    assert_eq!(SmartSwap::get_condition(env.clone(), increase_id).unwrap().reference_price, 1000000);
+    assert_eq!(SmartSwap::get_condition(env.clone(), decrease_id).unwrap().reference_price, 1000000);
+    assert_eq!(SmartSwap::get_condition(env.clone(), threshold_id).unwrap().reference_price, 500000);
+    assert_eq!(SmartSwap::get_condition(env.clone(), cancelled_id).unwrap().reference_price, 500000);
+}
+
+#[test]
+fn test_swap_output_large_amount_no_overflow() {
+    let env = Env::default();
+    let dex_config = DexConfigManager::create_default_config(&env, Address::generate(&env));
+    let xlm = Symbol::new(&env, "XLM");
+    let btc = Symbol::new(&env, "BTC");
+
+    // The largest allowed amount times the simulated XLM/BTC reserves overflows u64
+    let amount_in = 1_000_000_0000000;
+    let pool_info = StellarDexIntegration::get_pool_info(&env, &dex_config, xlm.clone(), btc.clone()).unwrap();
+    let quote = StellarDexIntegration::get_swap_quote(&env, &dex_config, xlm, btc, amount_in).unwrap();
+
+    let amount_in_with_fee = amount_in as u128 * (10000 - pool_info.fee_rate) as u128 / 10000;
+    let expected = amount_in_with_fee * pool_info.reserve_b as u128 / (pool_info.reserve_a as u128 + amount_in_with_fee);
+    assert_eq!(quote.amount_out as u128, expected);
+    assert!(quote.amount_out > 0);
+    assert!(quote.amount_out < pool_info.reserve_b);
+    assert!(quote.price_impact > MAX_PRICE_IMPACT_BPS);
+}
+
+#[test]
+fn test_multi_hop_gas_estimate() {
+    let env = Env::default();
+    let dex_config = DexConfigManager::create_default_config(&env, Address::generate(&env));
+
+    let quote = StellarDexIntegration::get_swap_quote(
+        &env,
+        &dex_config,
+        Symbol::new(&env, "BTC"),
+        Symbol::new(&env, "ETH"),
+        1_0000000,
+    )
+    .unwrap();
+    let direct_path = quote.route.clone();
+    assert_eq!(direct_path.pool_addresses.len(), 1);
+    assert_eq!(quote.estimated_gas, StellarDexIntegration::estimate_gas(&dex_config, &direct_path));
+
+    // Routing through XLM adds a second pool
+    let xlm = Symbol::new(&env, "XLM");
+    let mut one_hop_path = direct_path.clone();
+    one_hop_path.intermediate_tokens = Vec::from_array(&env, [xlm.clone()]);
+    one_hop_path.pool_addresses = Vec::from_array(
+        &env,
+        [
+            StellarDexIntegration::calculate_pool_address(&env, &dex_config, &direct_path.token_in, &xlm),
+            StellarDexIntegration::calculate_pool_address(&env, &dex_config, &xlm, &direct_path.token_out),
+        ],
+    );
+
+    let direct_gas = StellarDexIntegration::estimate_gas(&dex_config, &direct_path);
+    let one_hop_gas = StellarDexIntegration::estimate_gas(&dex_config, &one_hop_path);
+    assert_eq!(one_hop_gas, direct_gas + 50_000);
+}
+
+#[test]
+fn test_min_gas_estimate_floor() {
+    let env = Env::default();
+    let mut dex_config = DexConfigManager::create_default_config(&env, Address::generate(&env));
+    assert_eq!(dex_config.min_gas_estimate, DEFAULT_MIN_GAS_ESTIMATE);
+
+    // Direct native -> issued swap estimates below a raised floor
+    let quote = StellarDexIntegration::get_swap_quote(
+        &env,
+        &dex_config,
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "USDC"),
+        100_0000000,
+    )
+    .unwrap();
+    assert!(quote.estimated_gas >= DEFAULT_MIN_GAS_ESTIMATE);
+    assert!(quote.estimated_gas < 500_000);
+
+    dex_config.min_gas_estimate = 500_000;
+    let quote = StellarDexIntegration::get_swap_quote(
+        &env,
+        &dex_config,
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "USDC"),
+        100_0000000,
+    )
+    .unwrap();
+    assert_eq!(quote.estimated_gas, 500_000);
+
+    dex_config.min_gas_estimate = 0;
+    assert_eq!(
+        DexConfigManager::validate_config(&env, &dex_config),
+        Err(Symbol::new(&env, "invalid_min_gas_estimate"))
+    );
+    dex_config.min_gas_estimate = MAX_MIN_GAS_ESTIMATE + 1;
+    assert!(DexConfigManager::validate_config(&env, &dex_config).is_err());
+}
+
+#[test]
+fn test_transfer_condition() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let new_owner = Address::generate(&env);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+
+    SmartSwap::transfer_condition(env.clone(), user.clone(), condition_id, new_owner.clone()).unwrap();
+
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.owner, new_owner);
+    assert_eq!(SmartSwap::get_user_conditions(env.clone(), user.clone()).len(), 0);
+    assert_eq!(SmartSwap::get_user_conditions(env.clone(), new_owner.clone()), Vec::from_array(&env, [condition_id]));
+
+    // The previous owner can no longer manage it, the new owner can
+    assert_eq!(
+        SmartSwap::cancel_condition(env.clone(), user.clone(), condition_id),
+        Err(Symbol::new(&env, "not_owner"))
+    );
+    SmartSwap::cancel_condition(env.clone(), new_owner.clone(), condition_id).unwrap();
+
+    // Cancelled conditions cannot be transferred
+    assert_eq!(
+        SmartSwap::transfer_condition(env.clone(), new_owner, condition_id, user),
+        Err(Symbol::new(&env, "cannot_transfer"))
+    );
+}
+
+#[test]
+fn test_transfer_condition_respects_recipient_limit() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let new_owner = Address::generate(&env);
+
+    let mut config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    config.max_conditions_per_user = 1;
+    env.storage().instance().set(&DataKey::Admin, &config);
+
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    SmartSwap::create_swap_condition(env.clone(), new_owner.clone(), create_test_swap_request(&env)).unwrap();
+
+    let result = SmartSwap::transfer_condition(env.clone(), user.clone(), condition_id, new_owner);
+    assert_eq!(result, Err(Symbol::new(&env, "condition_limit_exceeded")));
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().owner, user);
+}
+
+#[test]
+fn test_transfer_condition_unauthorized() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let attacker = Address::generate(&env);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+
+    let result = SmartSwap::transfer_condition(env.clone(), attacker.clone(), condition_id, attacker);
+    assert_eq!(result, Err(Symbol::new(&env, "not_owner")));
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().owner, user);
+}
+
+#[test]
+fn test_get_closest_to_trigger() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let xlm = Symbol::new(&env, "XLM");
+    assert_eq!(SmartSwap::get_closest_to_trigger(env.clone(), xlm.clone()), None);
+
+    // Live XLM price is 120000
+    let distances = [
+        SwapConditionType::PriceAbove(200000),
+        SwapConditionType::PriceBelow(110000),
+        SwapConditionType::PriceAbove(125000),
+        SwapConditionType::PriceBelow(100000),
+    ];
+    let mut ids = Vec::new(&env);
+    for condition_type in distances.iter() {
+        let mut request = create_test_swap_request(&env);
+        request.condition_type = condition_type.clone();
+        ids.push_back(SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap());
+    }
+
+    assert_eq!(SmartSwap::get_closest_to_trigger(env.clone(), xlm.clone()), Some(ids.get(2).unwrap()));
+
+    // Inactive conditions are ignored
+    SmartSwap::cancel_condition(env.clone(), user.clone(), ids.get(2).unwrap()).unwrap();
+    assert_eq!(SmartSwap::get_closest_to_trigger(env.clone(), xlm), Some(ids.get(1).unwrap()));
+
+    assert_eq!(SmartSwap::get_closest_to_trigger(env.clone(), Symbol::new(&env, "BTC")), None);
+}
+
+#[test]
+fn test_price_history_window_rolls() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let xlm = Symbol::new(&env, "XLM");
+    assert_eq!(SmartSwap::get_price_history(env.clone(), xlm.clone()).len(), 0);
+
+    // Trigger is far above the live price, so every check only observes the price
+    let mut request = create_test_swap_request(&env);
+    request.condition_type = SwapConditionType::PriceAbove(200000);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+    let start = env.ledger().timestamp();
+    assert_eq!(SmartSwap::get_price_history(env.clone(), xlm.clone()).len(), 1);
+
+    for _ in 0..(DEFAULT_TWAP_MAX_SAMPLES + 2) {
+        env.ledger().with_mut(|li| li.timestamp += 60);
+        let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id);
+        assert_eq!(result, Ok(None));
+    }
+
+    let history = SmartSwap::get_price_history(env.clone(), xlm.clone());
+    assert_eq!(history.len(), DEFAULT_TWAP_MAX_SAMPLES);
+    // The creation sample and the first two checks have rolled out of the window
+    assert_eq!(history.get(0).unwrap().timestamp, start + 3 * 60);
+    assert_eq!(history.last().unwrap().timestamp, env.ledger().timestamp());
+    assert!(history.iter().all(|observation| observation.asset_symbol == xlm && observation.price == 120000));
+
+    assert_eq!(SmartSwap::get_price_history(env.clone(), Symbol::new(&env, "BTC")).len(), 0);
+}
+
+#[test]
+fn test_min_confidence_override() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let mut invalid_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    invalid_request.min_confidence_override = Some(101);
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), invalid_request),
+        Err(Symbol::new(&env, "invalid_min_confidence"))
+    );
+
+    // Oracle confidence is 85, above the global floor of 70 but below the override
+    let mut strict_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    strict_request.min_confidence_override = Some(90);
+    let strict_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), strict_request).unwrap();
+
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), strict_id);
+    assert_eq!(result, Ok(None));
+    assert_eq!(SmartSwap::get_condition(env.clone(), strict_id).unwrap().status, SwapStatus::Active);
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10), Vec::from_array(&env, [strict_id]));
+
+    // An override looser than the global floor doesn't weaken it
+    let mut loose_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    loose_request.min_confidence_override = Some(50);
+    let loose_id = SmartSwap::create_swap_condition(env.clone(), user, loose_request).unwrap();
+    let condition = SmartSwap::get_condition(env.clone(), loose_id).unwrap();
+    assert_eq!(condition.effective_min_confidence(70), 70);
+
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, loose_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.success);
+}
+
+#[test]
+fn test_percentage_threshold_boundaries() {
+    let env = Env::default();
+
+    // 7% of 100001 is 7000.07, so the exact increase threshold is 107001.07
+    let mut condition = create_test_condition(&env, SwapConditionType::PercentageIncrease(7));
+    condition.reference_price = 100001;
+    assert!(!condition.should_execute(107001));
+    assert!(condition.should_execute(107002));
+    assert_eq!(condition.trigger_price(), Some(107002));
+
+    // The exact decrease threshold is 93000.93
+    let mut condition = create_test_condition(&env, SwapConditionType::PercentageDecrease(7));
+    condition.reference_price = 100001;
+    assert!(!condition.should_execute(93001));
+    assert!(condition.should_execute(93000));
+    assert_eq!(condition.trigger_price(), Some(93000));
+
+    // Evenly divisible thresholds execute at exactly the required move
+    let mut condition = create_test_condition(&env, SwapConditionType::PercentageIncrease(3));
+    condition.reference_price = 100000;
+    assert!(!condition.should_execute(102999));
+    assert!(condition.should_execute(103000));
+
+    let mut condition = create_test_condition(&env, SwapConditionType::PercentageDecrease(3));
+    condition.reference_price = 100000;
+    assert!(!condition.should_execute(97001));
+    assert!(condition.should_execute(97000));
+}
+
+#[test]
+fn test_get_pool_info() {
+    let env = Env::default();
+    let result = SmartSwap::get_pool_info(env.clone(), Symbol::new(&env, "XLM"), Symbol::new(&env, "USDC"));
+    assert_eq!(result, Err(Symbol::new(&env, "not_initialized")));
+
+    let (env, _admin, _user, _oracle) = create_test_env();
+    let pool = SmartSwap::get_pool_info(env.clone(), Symbol::new(&env, "XLM"), Symbol::new(&env, "USDC")).unwrap();
+    assert_eq!(pool.token_a, Symbol::new(&env, "XLM"));
+    assert_eq!(pool.token_b, Symbol::new(&env, "USDC"));
+    assert_eq!(pool.asset_a, AssetType::Native);
+    // Simulated XLM/USDC pool holds 10M XLM and 1.2M USDC
+    assert_eq!(pool.reserve_a, 10_000_000_0000000);
+    assert_eq!(pool.reserve_b, 1_200_000_000000);
+    assert!(pool.fee_rate > 0);
+}
+
+#[test]
+fn test_validate_request_matches_creation() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let mut bad_requests = Vec::new(&env);
+    let mut request = create_test_swap_request(&env);
+    request.amount_to_swap = 1;
+    bad_requests.push_back(request);
+    let mut request = create_test_swap_request(&env);
+    request.max_slippage = 10001;
+    bad_requests.push_back(request);
+    let mut request = create_test_swap_request(&env);
+    request.expires_at = env.ledger().timestamp();
+    bad_requests.push_back(request);
+    let mut request = create_test_swap_request(&env);
+    request.destination_asset = Symbol::new(&env, "XLM");
+    bad_requests.push_back(request);
+    let mut request = create_test_swap_request(&env);
+    request.source_asset = Symbol::new(&env, "DOGE");
+    bad_requests.push_back(request);
+    let mut request = create_test_swap_request(&env);
+    request.max_price_impact_bps = 1;
+    request.amount_to_swap = 1_000_000_0000000;
+    bad_requests.push_back(request);
+
+    for request in bad_requests.iter() {
+        let validation = SmartSwap::validate_request(env.clone(), request.clone());
+        assert!(validation.is_err());
+        let creation = SmartSwap::create_swap_condition(env.clone(), user.clone(), request);
+        assert_eq!(creation.map(|_| ()), validation);
+    }
+
+    // A valid request passes without creating anything
+    let request = create_test_swap_request(&env);
+    assert_eq!(SmartSwap::validate_request(env.clone(), request.clone()), Ok(()));
+    assert_eq!(SmartSwap::get_user_conditions(env.clone(), user.clone()).len(), 0);
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), request.source_asset.clone()), 0);
+    assert!(SmartSwap::create_swap_condition(env.clone(), user, request).is_ok());
+}
+
+#[test]
+fn test_pool_address_is_deterministic() {
+    let env = Env::default();
+    let dex_config = DexConfigManager::create_default_config(&env, Address::generate(&env));
+    let xlm = Symbol::new(&env, "XLM");
+    let usdc = Symbol::new(&env, "USDC");
+    let eth = Symbol::new(&env, "ETH");
+
+    let pool = StellarDexIntegration::calculate_pool_address(&env, &dex_config, &xlm, &usdc);
+    assert_eq!(pool, StellarDexIntegration::calculate_pool_address(&env, &dex_config, &usdc, &xlm));
+    assert_eq!(pool, StellarDexIntegration::calculate_pool_address(&env, &dex_config, &xlm, &usdc));
+    assert_ne!(pool, StellarDexIntegration::calculate_pool_address(&env, &dex_config, &xlm, &eth));
+
+    // Pool info and routing agree on the pair's pool
+    let pool_info = StellarDexIntegration::get_pool_info(&env, &dex_config, usdc.clone(), xlm.clone()).unwrap();
+    assert_eq!(pool_info.pool_address, pool);
+}
+
+#[test]
+fn test_min_amount_out_recomputed_at_execution() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+
+    // Mock USDC price is 1000000, 10% above a reference of 900000
+    let request = create_executable_swap_request(&env, SwapConditionType::PercentageIncrease(10));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+    set_reference_price(&env, condition_id, 900000);
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert!(condition.should_execute(1000000));
+
+    let params = SmartSwap::build_swap_params(&env, &config, &condition, 1000000);
+    // 100 USDC scaled by 10/9, less 5% slippage
+    assert_eq!(params.amount_out_min, 105_5555555);
+    assert!(params.amount_out_min > condition.min_amount_out);
+
+    // The stored minimum still records the creation-time intent
+    assert_eq!(condition.min_amount_out, 95_0000000);
+
+    // At the reference price the execution minimum matches the stored one
+    let params = SmartSwap::build_swap_params(&env, &config, &condition, 900000);
+    assert_eq!(params.amount_out_min, condition.min_amount_out);
+}
+
+#[test]
+fn test_min_amount_out_override() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+
+    let mut zero_request = create_executable_swap_request(&env, SwapConditionType::PercentageIncrease(10));
+    zero_request.min_amount_out_override = Some(0);
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), zero_request),
+        Err(Symbol::new(&env, "invalid_min_amount_out"))
+    );
+
+    let mut absurd_request = create_executable_swap_request(&env, SwapConditionType::PercentageIncrease(10));
+    absurd_request.min_amount_out_override = Some(absurd_request.amount_to_swap * MAX_MIN_OUT_MULTIPLIER + 1);
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), absurd_request),
+        Err(Symbol::new(&env, "invalid_min_amount_out"))
+    );
+
+    // Without an override the minimum is derived from max_slippage
+    let derived_request = create_executable_swap_request(&env, SwapConditionType::PercentageIncrease(10));
+    let derived_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), derived_request).unwrap();
+    let derived = SmartSwap::get_condition(env.clone(), derived_id).unwrap();
+    assert_eq!(derived.min_amount_out, 95_0000000);
+
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PercentageIncrease(10));
+    request.min_amount_out_override = Some(98_0000000);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.min_amount_out, 98_0000000);
+
+    // The explicit minimum isn't rescaled by the execution price
+    let params = SmartSwap::build_swap_params(&env, &config, &condition, 1100000);
+    assert_eq!(params.amount_out_min, 98_0000000);
+
+    // Loosening slippage leaves the explicit minimum in place
+    SmartSwap::update_condition(
+        env.clone(),
+        user,
+        condition_id,
+        1000,
+        condition.expires_at,
+        condition.condition_type.clone(),
+    )
+    .unwrap();
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().min_amount_out, 98_0000000);
+}
+
+#[test]
+fn test_retryable_execution_failure() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+
+    let mut invalid_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    invalid_request.max_retries = MAX_EXECUTION_RETRIES + 1;
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), invalid_request),
+        Err(Symbol::new(&env, "invalid_max_retries"))
+    );
+
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.max_retries = 1;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    // Liquidity dries up after creation, a transient failure
+    let mut shallow_config = config.dex_config.clone();
+    shallow_config.min_liquidity = u64::MAX;
+    SmartSwap::update_dex_config(env.clone(), admin.clone(), shallow_config).unwrap();
+
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id);
+    assert_eq!(result, Ok(None));
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.status, SwapStatus::Active);
+    assert_eq!(condition.retry_count, 1);
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10), Vec::from_array(&env, [condition_id]));
+
+    // Liquidity returns and the retry succeeds
+    SmartSwap::update_dex_config(env.clone(), admin, config.dex_config).unwrap();
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.success);
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.status, SwapStatus::Executed);
+    assert_eq!(condition.retry_count, 0);
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10).len(), 0);
+}
+
+#[test]
+fn test_retries_exhausted_marks_failed() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.max_retries = 1;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    let mut shallow_config = config.dex_config.clone();
+    shallow_config.min_liquidity = u64::MAX;
+    SmartSwap::update_dex_config(env.clone(), admin, shallow_config).unwrap();
+
+    assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id), Ok(None));
+    assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id), Ok(None));
+
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.status, SwapStatus::Failed);
+    assert_eq!(condition.retry_count, 2);
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10).len(), 0);
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).active_conditions_count, 0);
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), Symbol::new(&env, "USDC")), 0);
+
+    // Invalid swaps aren't worth retrying
+    assert!(SmartSwap::is_permanent_failure(&env, &Symbol::new(&env, "identical_tokens")));
+    assert!(!SmartSwap::is_permanent_failure(&env, &Symbol::new(&env, "insufficient_liquidity")));
+}
+
+#[test]
+fn test_oracle_priced_execution_mode() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let usdc = Symbol::new(&env, "USDC");
+    let eth = Symbol::new(&env, "ETH");
+
+    // The default pool prices USDC and ETH 1:1 while the oracle values ETH at 3000 USDC
+    let dex_out = StellarDexIntegration::get_swap_quote(&env, &config.dex_config, usdc.clone(), eth.clone(), 100_0000000)
+        .unwrap()
+        .amount_out;
+    let oracle_out = PriceOracleClient::estimate_swap_output(
+        &env,
+        &config.oracle_config,
+        usdc,
+        eth,
+        100_0000000,
+        DEFAULT_ASSET_DECIMALS,
+        DEFAULT_ASSET_DECIMALS,
+    )
+    .unwrap();
+    assert!(oracle_out < dex_out);
+
+    let mut dex_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    dex_request.min_amount_out_override = Some(1);
+    let dex_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), dex_request).unwrap();
+
+    let mut oracle_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    oracle_request.min_amount_out_override = Some(1);
+    oracle_request.execution_mode = ExecutionMode::OraclePriced;
+    let oracle_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), oracle_request).unwrap();
+
+    assert_eq!(SmartSwap::simulate_execution(env.clone(), oracle_id).unwrap().amount_out, oracle_out);
+
+    let dex_execution = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), dex_id)
+        .unwrap()
+        .unwrap();
+    let oracle_execution = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), oracle_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(dex_execution.amount_out, dex_out);
+    assert_eq!(oracle_execution.amount_out, oracle_out);
+
+    // The minimum output still applies to the oracle price
+    let mut strict_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    strict_request.min_amount_out_override = Some(oracle_out + 1);
+    strict_request.execution_mode = ExecutionMode::OraclePriced;
+    let strict_id = SmartSwap::create_swap_condition(env.clone(), user, strict_request).unwrap();
+
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper, strict_id);
+    assert_eq!(result, Ok(None));
+    assert_eq!(SmartSwap::get_condition(env.clone(), strict_id).unwrap().status, SwapStatus::Active);
+}
+
+#[test]
+fn test_asset_decimals_scale_oracle_output() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let usdc = Symbol::new(&env, "USDC");
+    let eth = Symbol::new(&env, "ETH");
+
+    assert_eq!(SmartSwap::get_asset_decimals(env.clone(), usdc.clone()), DEFAULT_ASSET_DECIMALS);
+    assert_eq!(
+        SmartSwap::set_asset_decimals(env.clone(), admin.clone(), eth.clone(), MAX_ASSET_DECIMALS + 1),
+        Err(Symbol::new(&env, "invalid_decimals"))
+    );
+    assert_eq!(
+        SmartSwap::set_asset_decimals(env.clone(), user.clone(), eth.clone(), 18),
+        Err(Symbol::new(&env, "unauthorized"))
+    );
+
+    SmartSwap::set_asset_decimals(env.clone(), admin.clone(), usdc.clone(), 6).unwrap();
+    SmartSwap::set_asset_decimals(env.clone(), admin, eth.clone(), 18).unwrap();
+    assert_eq!(SmartSwap::get_asset_decimals(env.clone(), usdc.clone()), 6);
+    assert_eq!(SmartSwap::get_asset_decimals(env.clone(), eth.clone()), 18);
+
+    // 3000 USDC at 6 decimals buys one ETH at 18 decimals
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let output = PriceOracleClient::estimate_swap_output(
+        &env,
+        &config.oracle_config,
+        usdc.clone(),
+        eth.clone(),
+        3000_000000,
+        6,
+        18,
+    )
+    .unwrap();
+    assert_eq!(output, 1_000000000000000000);
+
+    // And back the other way
+    let output = PriceOracleClient::estimate_swap_output(
+        &env,
+        &config.oracle_config,
+        eth.clone(),
+        usdc.clone(),
+        1_000000000000000000,
+        18,
+        6,
+    )
+    .unwrap();
+    assert_eq!(output, 3000_000000);
+
+    // The exchange rate is quoted in destination base units per whole source unit
+    let rate =
+        PriceOracleClient::calculate_exchange_rate(&env, &config.oracle_config, usdc.clone(), eth.clone(), 6, 18)
+            .unwrap();
+    assert_eq!(rate, 333333333333333);
+    let rate = PriceOracleClient::calculate_exchange_rate(&env, &config.oracle_config, eth, usdc, 18, 6).unwrap();
+    assert_eq!(rate, 3000_000000);
+
+    // Rate triggers read the registered decimals
+    let request = create_executable_swap_request(&env, SwapConditionType::RateBelow(1));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(SmartSwap::trigger_input(&env, &config, &condition, 1000000), Ok(333333333333333));
+}
+
+#[test]
+fn test_reference_price_guard() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    // The mock XLM/USDC pool pays about a tenth of what the oracle rate implies
+    let mut guarded_request = create_test_swap_request(&env);
+    guarded_request.condition_type = SwapConditionType::PriceAbove(1);
+    guarded_request.min_amount_out_override = Some(1);
+    guarded_request.enforce_reference_price = true;
+    let guarded_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), guarded_request).unwrap();
+
+    let condition = SmartSwap::get_condition(env.clone(), guarded_id).unwrap();
+    assert!(condition.is_sell_higher());
+    assert!(condition.fill_below_reference_price(120000, 10, 100));
+    assert!(!condition.fill_below_reference_price(120000, 96, 100));
+
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), guarded_id);
+    assert_eq!(result, Ok(None));
+    let condition = SmartSwap::get_condition(env.clone(), guarded_id).unwrap();
+    assert_eq!(condition.status, SwapStatus::Active);
+    assert_eq!(condition.retry_count, 0);
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10), Vec::from_array(&env, [guarded_id]));
+
+    // Without the guard only min_amount_out applies and the fill goes through
+    let mut unguarded_request = create_test_swap_request(&env);
+    unguarded_request.condition_type = SwapConditionType::PriceAbove(1);
+    unguarded_request.min_amount_out_override = Some(1);
+    let unguarded_id = SmartSwap::create_swap_condition(env.clone(), user, unguarded_request).unwrap();
+
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, unguarded_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.success);
+}
+
+#[test]
+fn test_oracle_dex_divergence_bound() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    assert_eq!(
+        SmartSwap::set_max_oracle_dex_divergence(env.clone(), user.clone(), 5000),
+        Err(Symbol::new(&env, "unauthorized"))
+    );
+    assert_eq!(SmartSwap::divergence_bps(90, 100), 1000);
+    assert_eq!(SmartSwap::divergence_bps(110, 100), 1000);
+
+    // The mock XLM/USDC pool pays about a tenth of the oracle rate, roughly 9000 bps off
+    SmartSwap::set_max_oracle_dex_divergence(env.clone(), admin.clone(), 5000).unwrap();
+    let mut request = create_test_swap_request(&env);
+    request.condition_type = SwapConditionType::PriceAbove(1);
+    request.min_amount_out_override = Some(1);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id);
+    assert_eq!(result, Ok(None));
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.status, SwapStatus::Active);
+    assert_eq!(condition.retry_count, 0);
+    assert_eq!(SmartSwap::get_retry_queue(env.clone(), 10), Vec::from_array(&env, [condition_id]));
+
+    // A looser bound lets the same fill through
+    SmartSwap::set_max_oracle_dex_divergence(env.clone(), admin, 9500).unwrap();
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.success);
+}
+
+#[test]
+fn test_withdraw_fees() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let treasury = Address::generate(&env);
+
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.protocol_fee > 0);
+    assert_eq!(SmartSwap::get_collected_fees(env.clone()), execution.protocol_fee);
+
+    assert_eq!(
+        SmartSwap::withdraw_fees(env.clone(), user, treasury.clone(), 1),
+        Err(Symbol::new(&env, "unauthorized"))
+    );
+    assert_eq!(
+        SmartSwap::withdraw_fees(env.clone(), admin.clone(), treasury.clone(), execution.protocol_fee + 1),
+        Err(Symbol::new(&env, "insufficient_fees"))
+    );
+
+    SmartSwap::withdraw_fees(env.clone(), admin.clone(), treasury.clone(), execution.protocol_fee - 1).unwrap();
+    assert_eq!(SmartSwap::get_collected_fees(env.clone()), 1);
+
+    SmartSwap::withdraw_fees(env.clone(), admin, treasury, 1).unwrap();
+    assert_eq!(SmartSwap::get_collected_fees(env.clone()), 0);
+}
+
+#[test]
+fn test_time_reached_condition() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+    let now = env.ledger().timestamp();
+
+    let past_request = create_executable_swap_request(&env, SwapConditionType::TimeReached(now));
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), past_request),
+        Err(Symbol::new(&env, "invalid_target_time"))
+    );
+    let mut late_request = create_executable_swap_request(&env, SwapConditionType::TimeReached(now + 7200));
+    late_request.expires_at = now + 3600;
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), late_request),
+        Err(Symbol::new(&env, "target_after_expiry"))
+    );
+
+    let request = create_executable_swap_request(&env, SwapConditionType::TimeReached(now + 600));
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    // Price is irrelevant, only the clock fires the trigger
+    env.ledger().with_mut(|li| li.timestamp += 599);
+    let result = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id);
+    assert_eq!(result, Ok(None));
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().execution_count, 0);
+
+    env.ledger().with_mut(|li| li.timestamp += 1);
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id)
+        .unwrap()
+        .unwrap();
+    assert!(execution.success);
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().status, SwapStatus::Executed);
+}
+
+#[test]
+fn test_executable_conditions_by_priority() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    // Mock USDC price is 1000000
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(900000));
+    let shallow_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    request.amount_to_swap = 500_0000000;
+    let large_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    request.amount_to_swap = 300_0000000;
+    let medium_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    // Same size as the shallow one, but the price is twice as far past the trigger
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(500000));
+    let deep_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    let request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(900000));
+    let tied_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    let condition = SmartSwap::get_condition(env.clone(), deep_id).unwrap();
+    assert_eq!(condition.execution_priority(1000000), 150_0000000);
+
+    let executable = SmartSwap::get_executable_conditions(env.clone(), 10);
+    assert_eq!(
+        executable,
+        Vec::from_array(&env, [large_id, medium_id, deep_id, shallow_id, tied_id])
+    );
+    assert_eq!(
+        SmartSwap::get_executable_conditions(env.clone(), 2),
+        Vec::from_array(&env, [large_id, medium_id])
+    );
+    assert_eq!(SmartSwap::get_executable_conditions(env.clone(), 0).len(), 0);
+}
+
+#[test]
+fn test_total_budget_caps_recurring_condition() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let mut invalid_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    invalid_request.total_budget = 1;
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), invalid_request),
+        Err(Symbol::new(&env, "invalid_total_budget"))
+    );
+
+    // 100 USDC per execution, unlimited executions, 250 USDC budget
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.max_executions = 0;
+    request.execution_interval = 60;
+    request.total_budget = 250_0000000;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, request).unwrap();
+
+    for _ in 0..2 {
+        env.ledger().with_mut(|li| li.timestamp += 60);
+        let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(execution.amount_in, 100_0000000);
+        assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().status, SwapStatus::Active);
+    }
+
+    // The last execution only spends what's left of the budget
+    env.ledger().with_mut(|li| li.timestamp += 60);
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(execution.amount_in, 50_0000000);
+
+    let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(condition.status, SwapStatus::Executed);
+    assert_eq!(condition.total_spent, 250_0000000);
+    assert_eq!(condition.execution_count, 3);
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), Symbol::new(&env, "USDC")), 0);
+    env.ledger().with_mut(|li| li.timestamp += 60);
+    assert_eq!(
+        SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id),
+        Err(Symbol::new(&env, "budget_exhausted"))
+    );
+
+    // Conditions executed for any other reason keep the generic error
+    let mut executed = condition;
+    executed.total_budget = 0;
+    let error = executed.is_valid(&env).unwrap_err();
+    assert_eq!((error.error_code, error.message), (1008, Symbol::new(&env, "condition_executed")));
+}
+
+#[test]
+fn test_get_remaining_executions() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    assert_eq!(
+        SmartSwap::get_remaining_executions(env.clone(), 999),
+        Err(Symbol::new(&env, "condition_not_found"))
+    );
+
+    let single_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    let single_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), single_request).unwrap();
+    assert_eq!(SmartSwap::get_remaining_executions(env.clone(), single_id), Ok(Some(1)));
+    SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), single_id).unwrap().unwrap();
+    assert_eq!(SmartSwap::get_remaining_executions(env.clone(), single_id), Ok(Some(0)));
+
+    let mut recurring_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    recurring_request.max_executions = 3;
+    recurring_request.execution_interval = 60;
+    let recurring_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), recurring_request).unwrap();
+    SmartSwap::check_and_execute_condition(env.clone(), keeper, recurring_id).unwrap().unwrap();
+    assert_eq!(SmartSwap::get_remaining_executions(env.clone(), recurring_id), Ok(Some(2)));
+
+    let mut unlimited_request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    unlimited_request.max_executions = 0;
+    unlimited_request.execution_interval = 60;
+    let unlimited_id = SmartSwap::create_swap_condition(env.clone(), user, unlimited_request).unwrap();
+    assert_eq!(SmartSwap::get_remaining_executions(env.clone(), unlimited_id), Ok(None));
+}
+
+#[test]
+fn test_min_check_interval() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    // XLM -> USDC 10% increase stays untriggered at the mock price
+    let request = create_test_swap_request(&env);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    // Disabled by default, back-to-back checks are allowed
+    assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id), Ok(None));
+    assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id), Ok(None));
+
+    assert!(SmartSwap::set_min_check_interval(env.clone(), user, 60).is_err());
+    assert_eq!(
+        SmartSwap::set_min_check_interval(env.clone(), admin.clone(), MAX_MIN_CHECK_INTERVAL_SECS + 1),
+        Err(Symbol::new(&env, "invalid_check_interval"))
+    );
+    SmartSwap::set_min_check_interval(env.clone(), admin, 60).unwrap();
+
+    assert_eq!(
+        SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id),
+        Err(Symbol::new(&env, "checked_too_soon"))
+    );
+
+    env.ledger().with_mut(|li| {
+        li.timestamp += 59;
+    });
+    assert_eq!(
+        SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id),
+        Err(Symbol::new(&env, "checked_too_soon"))
+    );
+
+    env.ledger().with_mut(|li| {
+        li.timestamp += 1;
+    });
+    assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id), Ok(None));
+    let last_check = SmartSwap::get_condition(env.clone(), condition_id).unwrap().last_check;
+    assert_eq!(last_check, env.ledger().timestamp());
+
+    // The successful check restarts the window
+    assert_eq!(
+        SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id),
+        Err(Symbol::new(&env, "checked_too_soon"))
+    );
+}
+
+#[test]
+fn test_get_conditions_by_expiry_bucket() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let now = env.ledger().timestamp();
+
+    let mut ids = Vec::new(&env);
+    for time_left in [3600, 86399, 86400, 3 * 86400, 30 * 86400] {
+        let mut request = create_test_swap_request(&env);
+        request.expires_at = now + time_left;
+        ids.push_back(SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap());
+    }
+
+    // Inactive conditions and other users' conditions are left out
+    let cancelled_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    SmartSwap::cancel_condition(env.clone(), user.clone(), cancelled_id).unwrap();
+    SmartSwap::create_swap_condition(env.clone(), Address::generate(&env), create_test_swap_request(&env)).unwrap();
+
+    let buckets = SmartSwap::get_conditions_by_expiry_bucket(env.clone(), user.clone());
+    assert_eq!(buckets.within_day, Vec::from_array(&env, [ids.get(0).unwrap(), ids.get(1).unwrap()]));
+    assert_eq!(buckets.within_week, Vec::from_array(&env, [ids.get(2).unwrap(), ids.get(3).unwrap()]));
+    assert_eq!(buckets.later, Vec::from_array(&env, [ids.get(4).unwrap()]));
+
+    // Buckets shift as time passes
+    env.ledger().with_mut(|li| {
+        li.timestamp += 2 * 86400;
+    });
+    let buckets = SmartSwap::get_conditions_by_expiry_bucket(env.clone(), user);
+    assert_eq!(buckets.within_day, Vec::from_array(&env, [ids.get(3).unwrap()]));
+    assert_eq!(buckets.within_week.len(), 0);
+    assert_eq!(buckets.later, Vec::from_array(&env, [ids.get(4).unwrap()]));
+}
+
+#[test]
+fn test_update_oracle_config_probe() {
+    let (env, admin, _user, _oracle) = create_test_env();
+    let original: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+
+    // Oracle data below the new confidence bar with no fallback can't serve prices
+    let mut broken_config = original.oracle_config.clone();
+    broken_config.min_confidence = 90;
+    broken_config.fallback_enabled = false;
+
+    let result = SmartSwap::update_oracle_config(env.clone(), admin.clone(), broken_config.clone(), false);
+    assert_eq!(result, Err(Symbol::new(&env, "oracle_probe_failed")));
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    assert_eq!(config.oracle_config, original.oracle_config);
+
+    // Forcing skips the probe
+    SmartSwap::update_oracle_config(env.clone(), admin.clone(), broken_config.clone(), true).unwrap();
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    assert_eq!(config.oracle_config, broken_config);
+
+    // A responsive oracle passes the probe
+    SmartSwap::update_oracle_config(env.clone(), admin, original.oracle_config.clone(), false).unwrap();
+}
+
+#[test]
+fn test_average_execution_price() {
+    let env = Env::default();
+    let mut condition = create_test_condition(&env, SwapConditionType::PriceAbove(1));
+    condition.chunk_size = 25_0000000;
+    assert_eq!(condition.average_execution_price, 0);
+
+    // The first fill sets the average outright
+    let first = SwapExecution::new(&env, 1, 100000, 25_0000000, 25_0000000, 0, Symbol::new(&env, "tx1"));
+    condition.update_execution(&env, &first);
+    assert_eq!(condition.average_execution_price, 100000);
+    assert_eq!(condition.total_filled, 25_0000000);
+
+    // 25 at 100000 and 75 at 130000 blend to 122500
+    let second = SwapExecution::new(&env, 1, 130000, 75_0000000, 75_0000000, 0, Symbol::new(&env, "tx2"));
+    condition.update_execution(&env, &second);
+    assert_eq!(condition.average_execution_price, 122500);
+    assert_eq!(condition.total_filled, 100_0000000);
+
+    // Extreme prices and volumes don't overflow the weighting
+    let mut condition = create_test_condition(&env, SwapConditionType::PriceAbove(1));
+    let huge = SwapExecution::new(&env, 1, u64::MAX, u64::MAX / 2, u64::MAX / 2, 0, Symbol::new(&env, "tx3"));
+    condition.update_execution(&env, &huge);
+    condition.update_execution(&env, &huge);
+    assert_eq!(condition.average_execution_price, u64::MAX);
+}
+
+#[test]
+fn test_admin_cancel_condition() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    let other_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).active_conditions_count, 2);
+
+    // Neither the owner nor anyone else besides the admin can use the escape hatch
+    assert_eq!(
+        SmartSwap::admin_cancel_condition(env.clone(), user.clone(), condition_id),
+        Err(Symbol::new(&env, "unauthorized"))
+    );
+    assert_eq!(
+        SmartSwap::admin_cancel_condition(env.clone(), Address::generate(&env), condition_id),
+        Err(Symbol::new(&env, "unauthorized"))
+    );
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().status, SwapStatus::Active);
+
+    SmartSwap::admin_cancel_condition(env.clone(), admin.clone(), condition_id).unwrap();
+
+    assert_eq!(SmartSwap::get_condition(env.clone(), condition_id).unwrap().status, SwapStatus::Cancelled);
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).active_conditions_count, 1);
+    assert_eq!(SmartSwap::get_user_conditions(env.clone(), user.clone()), Vec::from_array(&env, [other_id]));
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), Symbol::new(&env, "XLM")), 100_0000000);
+
+    assert_eq!(
+        SmartSwap::admin_cancel_condition(env.clone(), admin, condition_id),
+        Err(Symbol::new(&env, "cannot_cancel"))
+    );
+}
+
+#[test]
+fn test_best_and_worst_execution_price() {
+    let env = Env::default();
+    let mut condition = create_test_condition(&env, SwapConditionType::PriceAbove(1));
+    condition.max_executions = 0;
+    assert_eq!(condition.best_execution_price, None);
+    assert_eq!(condition.worst_execution_price, None);
+
+    for (price, best, worst) in [(120000, 120000, 120000), (150000, 150000, 120000), (90000, 150000, 90000), (130000, 150000, 90000)] {
+        let execution = SwapExecution::new(&env, 1, price, 100_0000000, 100_0000000, 0, Symbol::new(&env, "tx"));
+        condition.update_execution(&env, &execution);
+        assert_eq!(condition.best_execution_price, Some(best));
+        assert_eq!(condition.worst_execution_price, Some(worst));
+    }
+    assert_eq!(condition.execution_count, 4);
+}
+
+#[test]
+fn test_volume_fee_tiers() {
+    let (env, admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    let mut invalid_tiers = Vec::new(&env);
+    invalid_tiers.push_back(FeeTier { min_volume: 500_0000000, fee_bps: 2 });
+    invalid_tiers.push_back(FeeTier { min_volume: 150_0000000, fee_bps: 1 });
+    assert_eq!(
+        SmartSwap::set_fee_tiers(env.clone(), admin.clone(), invalid_tiers),
+        Err(Symbol::new(&env, "invalid_fee_tiers"))
+    );
+
+    let mut tiers = Vec::new(&env);
+    tiers.push_back(FeeTier { min_volume: 150_0000000, fee_bps: 2 });
+    tiers.push_back(FeeTier { min_volume: 1000_0000000, fee_bps: 1 });
+    assert!(SmartSwap::set_fee_tiers(env.clone(), user.clone(), tiers.clone()).is_err());
+    SmartSwap::set_fee_tiers(env.clone(), admin, tiers).unwrap();
+
+    let mut request = create_executable_swap_request(&env, SwapConditionType::PriceAbove(1));
+    request.max_executions = 3;
+    request.execution_interval = 60;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+
+    // Base fee of 5 bps on 100 USDC until the user crosses 150 USDC of volume
+    for _ in 0..2 {
+        env.ledger().with_mut(|li| li.timestamp += 60);
+        let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper.clone(), condition_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(execution.protocol_fee, 500000);
+    }
+    assert_eq!(SmartSwap::get_user_volume(env.clone(), user.clone()), 200_0000000);
+    assert_eq!(
+        SmartSwap::get_user_fee_tier(env.clone(), user.clone()),
+        Some(FeeTier { min_volume: 150_0000000, fee_bps: 2 })
+    );
+
+    env.ledger().with_mut(|li| li.timestamp += 60);
+    let execution = SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(execution.protocol_fee, 200000);
+    assert_eq!(SmartSwap::get_user_fee_tier(env.clone(), Address::generate(&env)), None);
+}
+
+#[test]
+fn test_required_slippage_for_now() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let keeper = Address::generate(&env);
+
+    // Trigger met, but the XLM/USDC pool pays far less than the oracle implies
+    let mut request = create_test_swap_request(&env);
+    request.condition_type = SwapConditionType::PriceAbove(1);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+    assert_eq!(SmartSwap::check_and_execute_condition(env.clone(), keeper, condition_id), Ok(None));
+
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let quote = StellarDexIntegration::get_swap_quote(
+        &env,
+        &config.dex_config,
+        Symbol::new(&env, "XLM"),
+        Symbol::new(&env, "USDC"),
+        100_0000000,
+    )
+    .unwrap();
+    let expected = 10000 - (quote.amount_out as u128 * 10000 / 100_0000000) as u32;
+
+    let required = SmartSwap::required_slippage_for_now(env.clone(), condition_id).unwrap();
+    assert_eq!(required, expected);
+    assert!(required > 500);
+
+    // Untriggered conditions have nothing to report
+    let untriggered_id = SmartSwap::create_swap_condition(env.clone(), user, create_test_swap_request(&env)).unwrap();
+    assert_eq!(
+        SmartSwap::required_slippage_for_now(env.clone(), untriggered_id),
+        Err(Symbol::new(&env, "trigger_not_met"))
+    );
+
+    // 97 out of an expected 100 needs exactly 3%, rounding up partial basis points
+    let condition = create_test_condition(&env, SwapConditionType::PriceAbove(1));
+    assert_eq!(condition.required_slippage_bps(100000, 97_0000000), 300);
+    assert_eq!(condition.required_slippage_bps(100000, 96_9500000), 305);
+    assert_eq!(condition.required_slippage_bps(100000, 96_9499999), 306);
+    assert_eq!(condition.required_slippage_bps(100000, 100_0000000), 0);
+}
+
+#[test]
+fn test_create_swap_conditions_batch() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let mut requests = Vec::new(&env);
+    for percentage in [5, 10, 15] {
+        let mut request = create_test_swap_request(&env);
+        request.condition_type = SwapConditionType::PercentageIncrease(percentage);
+        requests.push_back(request);
+    }
+
+    let ids = SmartSwap::create_swap_conditions(env.clone(), user.clone(), requests).unwrap();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(SmartSwap::get_user_conditions(env.clone(), user.clone()), ids);
+    assert_eq!(
+        SmartSwap::get_condition(env.clone(), ids.get(2).unwrap()).unwrap().condition_type,
+        SwapConditionType::PercentageIncrease(15)
+    );
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).active_conditions_count, 3);
+}
+
+#[test]
+fn test_create_swap_conditions_batch_respects_cap() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let mut config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    config.max_conditions_per_user = 3;
+    env.storage().instance().set(&DataKey::Admin, &config);
+    SmartSwap::create_swap_condition(env.clone(), user.clone(), create_test_swap_request(&env)).unwrap();
+
+    // Two more fit on their own, the batch of three doesn't
+    let mut requests = Vec::new(&env);
+    for _ in 0..3 {
+        requests.push_back(create_test_swap_request(&env));
+    }
+    let result = SmartSwap::create_swap_conditions(env.clone(), user.clone(), requests.clone());
+    assert_eq!(result, Err(Symbol::new(&env, "condition_limit_exceeded")));
+    assert_eq!(SmartSwap::get_user_conditions(env.clone(), user.clone()).len(), 1);
+
+    requests.pop_back();
+    assert_eq!(SmartSwap::create_swap_conditions(env.clone(), user.clone(), requests).unwrap().len(), 2);
+}
+
+#[test]
+fn test_create_swap_conditions_batch_reverts_on_invalid_request() {
+    let (env, _admin, user, _oracle) = create_test_env();
+
+    let mut requests = Vec::new(&env);
+    requests.push_back(create_test_swap_request(&env));
+    let mut invalid_request = create_test_swap_request(&env);
+    invalid_request.max_slippage = 10001;
+    requests.push_back(invalid_request);
+    requests.push_back(create_test_swap_request(&env));
+
+    let result = SmartSwap::create_swap_conditions(env.clone(), user.clone(), requests);
+    assert!(result.is_err());
+    assert_eq!(SmartSwap::get_user_conditions(env.clone(), user.clone()).len(), 0);
+    assert_eq!(SmartSwap::get_global_stats(env.clone()).total_conditions_created, 0);
+    assert_eq!(SmartSwap::get_pending_volume(env.clone(), Symbol::new(&env, "XLM")), 0);
+
+    assert_eq!(
+        SmartSwap::create_swap_conditions(env.clone(), user, Vec::new(&env)),
+        Err(Symbol::new(&env, "invalid_batch_size"))
+    );
+}
+
+#[test]
+fn test_get_condition_status() {
+    let (env, admin, user, _oracle) = create_test_env();
+
+    let report = SmartSwap::get_condition_status(env.clone(), 999);
+    assert_eq!(report.status, None);
+    assert_eq!(report.error, Some(Symbol::new(&env, "condition_not_found")));
+    assert!(!report.would_execute);
+
+    // Live XLM price is 120000
+    let condition_types = [
+        SwapConditionType::PriceAbove(100000),
+        SwapConditionType::PriceAbove(150000),
+        SwapConditionType::PriceBelow(150000),
+        SwapConditionType::PercentageIncrease(10),
+        SwapConditionType::TargetPrice(121000, 100),
+    ];
+    for condition_type in condition_types.iter() {
+        let mut request = create_test_swap_request(&env);
+        request.condition_type = condition_type.clone();
+        let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request).unwrap();
+        let condition = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+
+        let report = SmartSwap::get_condition_status(env.clone(), condition_id);
+        assert_eq!(report.status, Some(SwapStatus::Active));
+        assert_eq!(report.current_price, Some(120000));
+        assert_eq!(report.price_timestamp, Some(env.ledger().timestamp()));
+        assert_eq!(report.confidence, Some(85));
+        assert_eq!(report.would_execute, condition.should_execute(120000));
+        assert!(report.projected_output > 0);
+        assert_eq!(report.error, None);
+    }
+
+    // Oracle failures are reported instead of trapping
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user, create_test_swap_request(&env)).unwrap();
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let mut broken_config = config.oracle_config.clone();
+    broken_config.min_confidence = 90;
+    broken_config.fallback_enabled = false;
+    SmartSwap::update_oracle_config(env.clone(), admin, broken_config, true).unwrap();
+
+    let report = SmartSwap::get_condition_status(env.clone(), condition_id);
+    assert_eq!(report.status, Some(SwapStatus::Active));
+    assert_eq!(report.current_price, None);
+    assert_eq!(report.price_timestamp, None);
+    assert_eq!(report.confidence, None);
+    assert!(!report.would_execute);
+    assert!(report.error.is_some());
+}
+
+#[test]
+fn test_slippage_above_dex_tolerance() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let config: ContractConfig = env.storage().instance().get(&DataKey::Admin).unwrap();
+    let dex_tolerance = config.dex_config.max_slippage_tolerance;
+    assert!(dex_tolerance < MAX_SLIPPAGE_BASIS_POINTS);
+
+    // Within the absolute cap but beyond what the DEX allows
+    let mut request = create_test_swap_request(&env);
+    request.max_slippage = dex_tolerance + 1;
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()),
+        Err(Symbol::new(&env, "slippage_above_dex_tolerance"))
+    );
+    assert_eq!(
+        SmartSwap::validate_request(env.clone(), request.clone()),
+        Err(Symbol::new(&env, "slippage_above_dex_tolerance"))
+    );
+
+    // The absolute cap still applies first
+    request.max_slippage = MAX_SLIPPAGE_BASIS_POINTS + 1;
+    assert_eq!(
+        SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()),
+        Err(Symbol::new(&env, "slippage_too_high"))
+    );
+
+    request.max_slippage = dex_tolerance;
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+
+    // Updates are held to the same bound
+    let result = SmartSwap::update_condition(
+        env.clone(),
+        user,
+        condition_id,
+        dex_tolerance + 1,
+        request.expires_at,
+        request.condition_type,
+    );
+    assert_eq!(result, Err(Symbol::new(&env, "slippage_above_dex_tolerance")));
+}
+
+#[test]
+fn test_extend_expiry() {
+    let (env, _admin, user, _oracle) = create_test_env();
+    let request = create_test_swap_request(&env);
+    let condition_id = SmartSwap::create_swap_condition(env.clone(), user.clone(), request.clone()).unwrap();
+    let original = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+
+    let new_expires_at = request.expires_at + 86400;
+    SmartSwap::extend_expiry(env.clone(), user.clone(), condition_id, new_expires_at).unwrap();
+    let extended = SmartSwap::get_condition(env.clone(), condition_id).unwrap();
+    assert_eq!(extended.expires_at, new_expires_at);
+    assert_eq!(extended.reference_price, original.reference_price);
+
+    // Shortening isn't an extension, and only the owner may extend
+    assert_eq!(
+        SmartSwap::extend_expiry(env.clone(), user.clone(), condition_id, request.expires_at),
+        Err(Symbol::new(&env, "invalid_expiry"))
+    );
+    assert_eq!(
+        SmartSwap::extend_expiry(env.clone(), Address::generate(&env), condition_id, new_expires_at + 1),
+        Err(Symbol::new(&env, "not_owner"))
+    );
+
+    // Capped at the maximum lifetime from creation
+    let max_expires_at = extended.created_at + MAX_CONDITION_LIFETIME;
+    assert_eq!(
+        SmartSwap::extend_expiry(env.clone(), user.clone(), condition_id, max_expires_at + 1),
+        Err(Symbol::new(&env, "lifetime_too_long"))
+    );
+    SmartSwap::extend_expiry(env.clone(), user.clone(), condition_id, max_expires_at).unwrap();
+
+    SmartSwap::cancel_condition(env.clone(), user.clone(), condition_id).unwrap();
+    assert_eq!(
+        SmartSwap::extend_expiry(env.clone(), user, condition_id, max_expires_at),
+        Err(Symbol::new(&env, "cannot_extend"))
+    );
+}